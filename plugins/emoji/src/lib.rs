@@ -0,0 +1,56 @@
+//! RMenu Emoji Plugin Implementation
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use rmenu_plugin::{Action, Entry, Plugin, Preview, Search};
+
+/// Retrieve Available Copy Command Arguments from System
+pub fn copy_args() -> Result<Vec<String>> {
+    let path = |p: PathBuf| p.to_string_lossy().to_string();
+    if let Ok(p) = which::which("wl-copy") {
+        Ok(vec![path(p)])
+    } else if let Ok(p) = which::which("xsel") {
+        Ok(vec![path(p), "-ib".to_owned()])
+    } else if let Ok(p) = which::which("xclip") {
+        Ok(vec![path(p), "-selection".to_owned(), "c".to_owned()])
+    } else {
+        Err(anyhow!("no copy command available!"))
+    }
+}
+
+/// Generate Emoji Entries Copied using the Specified Command
+pub fn entries(copy: &str) -> Vec<Entry> {
+    emojis::iter()
+        .map(|emoji| {
+            let action = format!("{copy} '{}'", emoji.as_str());
+            let mut entry = Entry::new(emoji.as_str(), &action, Some(emoji.name()));
+            entry.preview = Some(Preview::Html(format!(
+                "<div class=\"emoji-preview\">{}</div><div>{}</div>",
+                emoji.as_str(),
+                emoji.name()
+            )));
+            entry
+        })
+        .collect()
+}
+
+/// In-Process Emoji Plugin
+///
+/// Emojis are echoed rather than copied when no copy command is available.
+#[derive(Debug, Default)]
+pub struct Emoji {}
+
+impl Plugin for Emoji {
+    fn search(&mut self, _search: &Search) -> Vec<Entry> {
+        match copy_args() {
+            Ok(args) => entries(&args.join(" ")),
+            Err(_) => entries("")
+                .into_iter()
+                .map(|mut e| {
+                    e.actions = vec![Action::echo(&e.name)];
+                    e
+                })
+                .collect(),
+        }
+    }
+}
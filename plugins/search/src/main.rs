@@ -1,35 +1,69 @@
 mod bang;
 mod pattern;
 
-use std::io::{BufRead, BufReader, Read};
+use std::collections::HashMap;
 
 use pattern::Patterns;
-use rmenu_plugin::{Entry, Message, Search};
+use regex::Regex;
+use rmenu_plugin::live::{self, Reply};
+use rmenu_plugin::{Entry, Hello};
 
 use crate::bang::Bang;
 
 static DEFAULT_BANG: &'static str = "!brave";
 
-fn send_entry(entry: &Entry) {
-    let message = serde_json::to_string(&entry).expect("invalid entry");
-    println!("{message}");
+/// Evaluate Search using Fend Calculator (if Possible)
+fn calculate(search: &str) -> Option<String> {
+    let mut fend = fend_core::Context::new();
+    fend.set_random_u32_fn(|| rand::random());
+    let result = fend_core::evaluate(search, &mut fend).ok()?;
+    let calc = result.get_main_result();
+    match calc.is_empty() {
+        true => None,
+        false => Some(calc.to_owned()),
+    }
 }
 
-fn read_search<T: Read>(reader: &mut BufReader<T>) -> Option<Search> {
-    let mut buf = String::new();
-    match reader.read_line(&mut buf) {
-        Ok(_) => {}
-        Err(err) => {
-            log::error!("failed to read stdin: {err:?}");
-            return None;
-        }
+/// Reply with Calculator, Pattern and Bang Search Results
+fn search(reply: &Reply, bangs: &HashMap<String, Bang>, rgx: &Regex, patterns: &Patterns) {
+    let search = reply.search();
+
+    // retrieve bang based on search
+    let search_bang = rgx.find(&search.search).map(|m| m.as_str());
+
+    // check if search can be processed by fend
+    if let Some(calc) = calculate(&search.search) {
+        let name = format!(" = {calc}");
+        let action = format!("wl-copy {calc:?}");
+        reply.send(Entry::new(&name, &action, None));
     }
-    buf = buf.trim().to_owned();
-    if buf.is_empty() {
-        log::debug!("message empty. breaking loop.");
-        return None;
+
+    // retrieve bang based on search
+    let search_bang = search_bang.unwrap_or(DEFAULT_BANG);
+    let bang = match bangs.get(&search_bang[1..]) {
+        Some(bang) => bang,
+        None => {
+            log::warn!("invalid bang: {search_bang:?}");
+            bangs.get(&DEFAULT_BANG[1..]).expect("default bang missing")
+        }
+    };
+
+    // remove bang from search string
+    let query = search.search.replace(search_bang, "");
+    let query = query.trim();
+
+    // attempt to match available patterns
+    if let Some(entries) = patterns.try_match(query, Some(bang)) {
+        for entry in entries {
+            reply.send(entry);
+        }
     }
-    serde_json::from_str(&buf).ok()
+
+    let name = format!("{} - {query}", bang.name);
+    let escaped = url_escape::encode_component(query).to_string();
+
+    let action = format!("xdg-open {:?}", bang.url.replace(r"{{{s}}}", &escaped));
+    reply.send(Entry::new(&name, &action, None));
 }
 
 fn main() {
@@ -40,67 +74,12 @@ fn main() {
         .build()
         .expect("bang regex failed");
 
-    // configure fend
-    let mut fend = fend_core::Context::new();
-    fend.set_random_u32_fn(|| rand::random());
-
     // configure additiona patterns
     let patterns = Patterns::new();
 
-    // prepare stdin reader
-    let stdin = std::io::stdin();
-    let mut reader = BufReader::new(stdin);
-
-    loop {
-        // read search message from stdin
-        let Some(search) = read_search(&mut reader) else {
-            continue;
-        };
-
-        // retrieve bang based on search
-        let search_bang = rgx.find(&search.search).map(|m| m.as_str());
-
-        // check if search can be processed by fend
-        if let Ok(result) = fend_core::evaluate(&search.search, &mut fend) {
-            let calc = result.get_main_result();
-            if !calc.is_empty() {
-                let name = format!(" = {calc}");
-                let action = format!("wl-copy {calc:?}");
-                let entry = Entry::new(&name, &action, None);
-                send_entry(&entry);
-            }
-        }
-
-        // retrieve bang based on search
-        let search_bang = search_bang.unwrap_or(DEFAULT_BANG);
-        let bang = match bangs.get(&search_bang[1..]) {
-            Some(bang) => bang,
-            None => {
-                log::warn!("invalid bang: {search_bang:?}");
-                bangs.get(&DEFAULT_BANG[1..]).expect("default bang missing")
-            }
-        };
-
-        // remove bang from search string
-        let query = search.search.replace(search_bang, "");
-        let query = query.trim();
-
-        // attempt to match available patterns
-        if let Some(entries) = patterns.try_match(query, Some(bang)) {
-            for entry in entries.iter() {
-                send_entry(&entry);
-            }
-        }
-
-        let name = format!("{} - {query}", bang.name);
-        let escaped = url_escape::encode_component(query).to_string();
-
-        let action = format!("xdg-open {:?}", bang.url.replace(r"{{{s}}}", &escaped));
-        let entry = Entry::new(&name, &action, None);
-        send_entry(&entry);
-
-        let stop = Message::Stop;
-        let message = serde_json::to_string(&stop).expect("invalid stop");
-        println!("{message}");
+    // reply to searches until stdin is closed
+    let handler = move |reply: &Reply| search(reply, &bangs, &rgx, &patterns);
+    if let Err(err) = live::run(Hello::new(true), handler) {
+        log::error!("failed to serve searches: {err:?}");
     }
 }
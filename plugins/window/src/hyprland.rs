@@ -1,7 +1,7 @@
 use std::process::{Command, Stdio};
 
 use anyhow::{anyhow, Context, Result};
-use rmenu_plugin::Entry;
+use rmenu_plugin::{Entry, Preview};
 use serde::Deserialize;
 
 use crate::WindowManager;
@@ -54,7 +54,10 @@ impl WindowManager for HyprlandManager {
             .into_iter()
             .map(|w| {
                 let exec = format!("{exe} focus {:?}", w.pid);
-                Entry::new(&w.title, &exec, None)
+                let preview = format!("ps -o pid,etime,args -p {}", w.pid);
+                let mut entry = Entry::new(&w.title, &exec, None);
+                entry.preview = Some(Preview::Command(preview));
+                entry
             })
             .collect();
         Ok(entries)
@@ -2,7 +2,7 @@
 use std::process::{Command, Stdio};
 
 use anyhow::{anyhow, Context, Result};
-use rmenu_plugin::Entry;
+use rmenu_plugin::{Entry, Preview};
 use serde::Deserialize;
 use serde_json::Value;
 
@@ -89,7 +89,10 @@ impl WindowManager for SwayManager {
             .into_iter()
             .map(|w| {
                 let exec = format!("{exe} focus {:?}", w.pid);
-                Entry::new(&w.name, &exec, None)
+                let preview = format!("ps -o pid,etime,args -p {}", w.pid);
+                let mut entry = Entry::new(&w.name, &exec, None);
+                entry.preview = Some(Preview::Command(preview));
+                entry
             })
             .collect();
         Ok(entries)
@@ -52,6 +52,40 @@ impl FromStr for ActionMode {
     }
 }
 
+/// Valid Preview Modes
+#[derive(Debug, Clone)]
+enum PreviewMode {
+    Text,
+    Html,
+    Image,
+    Command,
+}
+
+impl Display for PreviewMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text => write!(f, "text"),
+            Self::Html => write!(f, "html"),
+            Self::Image => write!(f, "image"),
+            Self::Command => write!(f, "command"),
+        }
+    }
+}
+
+impl FromStr for PreviewMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "html" => Ok(Self::Html),
+            "image" => Ok(Self::Image),
+            "command" => Ok(Self::Command),
+            _ => Err(format!("Invalid Preview: {s:?}")),
+        }
+    }
+}
+
 /// Arguents for Action CLI Command
 #[derive(Debug, Args)]
 struct ActionArgs {
@@ -69,13 +103,13 @@ struct ActionArgs {
     mode: ActionMode,
 }
 
-impl Into<Action> for ActionArgs {
-    fn into(self) -> Action {
-        let exec = self.args.join(" ");
+impl From<ActionArgs> for Action {
+    fn from(args: ActionArgs) -> Self {
+        let exec = args.args.join(" ");
         Action {
-            name: self.name,
-            comment: self.comment,
-            exec: match self.mode {
+            name: args.name,
+            comment: args.comment,
+            exec: match args.mode {
                 ActionMode::Run => Method::Run(exec),
                 ActionMode::Terminal => Method::Terminal(exec),
                 ActionMode::Echo => Method::Echo(exec),
@@ -103,16 +137,28 @@ struct EntryArgs {
     /// Alternative Image Text/HTML
     #[arg(short = 'I', long)]
     icon_alt: Option<String>,
+    /// Preview Content for Entry
+    #[arg(short = 'v', long)]
+    preview: Option<String>,
+    /// Preview Content Mode
+    #[arg(short = 'V', long, default_value_t=PreviewMode::Text)]
+    preview_mode: PreviewMode,
 }
 
-impl Into<Entry> for EntryArgs {
-    fn into(self) -> Entry {
+impl From<EntryArgs> for Entry {
+    fn from(args: EntryArgs) -> Self {
         Entry {
-            name: self.name,
-            comment: self.comment,
-            actions: self.actions,
-            icon: self.icon,
-            icon_alt: self.icon_alt,
+            name: args.name,
+            comment: args.comment,
+            actions: args.actions,
+            icon: args.icon,
+            icon_alt: args.icon_alt,
+            preview: args.preview.map(|p| match args.preview_mode {
+                PreviewMode::Text => Preview::Text(p),
+                PreviewMode::Html => Preview::Html(p),
+                PreviewMode::Image => Preview::Image(p),
+                PreviewMode::Command => Preview::Command(p),
+            }),
         }
     }
 }
@@ -141,12 +187,24 @@ struct OptionArgs {
     /// Override Right-Click Context-Menu Activation Option
     #[arg(long)]
     pub context_menu: Option<bool>,
+    /// Override Multi-Select Option
+    #[arg(long)]
+    pub multi_select: Option<bool>,
+    /// Override Modal (Normal Mode) Keybinds Option
+    #[arg(long)]
+    pub modal: Option<bool>,
+    /// Override Group-Results Option
+    #[arg(long)]
+    pub group_results: Option<bool>,
     /// Override Use-Icons Setting
     #[arg(long)]
     pub use_icons: Option<bool>,
     /// Override Use-Comments Setting
     #[arg(long)]
     pub use_comments: Option<bool>,
+    /// Override Use-Preview Setting
+    #[arg(long)]
+    pub use_preview: Option<bool>,
 
     // search settings
     /// Override Default Placeholder
@@ -158,6 +216,9 @@ struct OptionArgs {
     /// Override Maximum Search Length
     #[arg(short = 'M', long)]
     pub search_max_length: Option<usize>,
+    /// Override Search Matching Mode
+    #[arg(short = 'm', long)]
+    pub search_mode: Option<String>,
     // key settings
     /// Override Execution Keybinds
     #[arg(short = 'e', long)]
@@ -189,6 +250,15 @@ struct OptionArgs {
     /// Override Mode-Previous Keybinds
     #[arg(long)]
     pub key_mode_prev: Option<Vec<String>>,
+    /// Override Toggle-Preview Keybinds
+    #[arg(long)]
+    pub key_toggle_preview: Option<Vec<String>>,
+    /// Override Toggle-Mark Keybinds
+    #[arg(long)]
+    pub key_toggle_mark: Option<Vec<String>>,
+    /// Override Normal-Mode Keybinds
+    #[arg(long)]
+    pub key_normal_mode: Option<Vec<String>>,
 
     // window settings
     /// Override Window Title
@@ -211,46 +281,89 @@ struct OptionArgs {
     pub window_height: Option<f64>,
 }
 
-impl Into<Options> for OptionArgs {
-    fn into(self) -> Options {
+impl From<OptionArgs> for Options {
+    fn from(args: OptionArgs) -> Self {
         Options {
-            css: self.css,
-            page_size: self.page_size,
-            page_load: self.page_load,
-            jump_dist: self.jump_dist,
-            placeholder: self.placeholder,
-            hover_select: self.hover_select,
-            single_click: self.single_click,
-            context_menu: self.context_menu,
-            use_icons: self.use_icons,
-            use_comments: self.use_comments,
+            css: args.css,
+            page_size: args.page_size,
+            page_load: args.page_load,
+            jump_dist: args.jump_dist,
+            placeholder: args.placeholder,
+            hover_select: args.hover_select,
+            single_click: args.single_click,
+            context_menu: args.context_menu,
+            multi_select: args.multi_select,
+            modal: args.modal,
+            group_results: args.group_results,
+            use_icons: args.use_icons,
+            use_comments: args.use_comments,
+            use_preview: args.use_preview,
 
-            search_restrict: self.search_restrict,
-            search_max_length: self.search_max_length,
-            key_exec: self.key_exec,
-            key_exit: self.key_exit,
-            key_move_next: self.key_move_next,
-            key_move_prev: self.key_move_prev,
-            key_open_menu: self.key_open_menu,
-            key_close_menu: self.key_close_menu,
-            key_jump_next: self.key_jump_next,
-            key_jump_prev: self.key_jump_prev,
-            key_mode_next: self.key_mode_next,
-            key_mode_prev: self.key_mode_prev,
+            search_restrict: args.search_restrict,
+            search_max_length: args.search_max_length,
+            search_mode: args.search_mode,
+            key_exec: args.key_exec,
+            key_exit: args.key_exit,
+            key_move_next: args.key_move_next,
+            key_move_prev: args.key_move_prev,
+            key_open_menu: args.key_open_menu,
+            key_close_menu: args.key_close_menu,
+            key_jump_next: args.key_jump_next,
+            key_jump_prev: args.key_jump_prev,
+            key_mode_next: args.key_mode_next,
+            key_mode_prev: args.key_mode_prev,
+            key_toggle_preview: args.key_toggle_preview,
+            key_toggle_mark: args.key_toggle_mark,
+            key_normal_mode: args.key_normal_mode,
 
-            title: self.title,
-            decorate: self.deocorate,
-            fullscreen: self.fullscreen,
-            transparent: self.transparent,
-            window_width: self.window_width,
-            window_height: self.window_height,
+            title: args.title,
+            decorate: args.deocorate,
+            fullscreen: args.fullscreen,
+            transparent: args.transparent,
+            window_width: args.window_width,
+            window_height: args.window_height,
         }
     }
 }
 
+/// Arguments for Hello CLI Command
+#[derive(Debug, Args)]
+struct HelloArgs {
+    /// Plugin Replies to Searches sent over Stdin
+    #[arg(short, long)]
+    live: bool,
+    /// Plugin Entries include Icons
+    #[arg(short, long)]
+    icons: bool,
+    /// Plugin Entries include Previews
+    #[arg(short, long)]
+    preview: bool,
+}
+
+impl From<HelloArgs> for Hello {
+    fn from(args: HelloArgs) -> Self {
+        Hello {
+            icons: args.icons,
+            preview: args.preview,
+            ..Hello::new(args.live)
+        }
+    }
+}
+
+/// Arguments for Stop CLI Command
+#[derive(Debug, Args)]
+struct StopArgs {
+    /// Search Request ID being Replied To
+    #[arg(short, long)]
+    id: Option<u64>,
+}
+
 /// Valid CLI Commands and their Arguments
 #[derive(Debug, Subcommand)]
+#[allow(clippy::large_enum_variant)] // parsed once per invocation
 enum Command {
+    /// Generate RMenu Capabilities Handshake
+    Hello(HelloArgs),
     /// Generate Complete RMenu Entry
     Entry(EntryArgs),
     /// Generate RMenu Entry Action Object
@@ -258,7 +371,7 @@ enum Command {
     /// Generate RMenu Options Settings
     Options(OptionArgs),
     /// Generate RMenu Stop Message
-    Stop,
+    Stop(StopArgs),
 }
 
 #[derive(Debug, Parser)]
@@ -273,6 +386,10 @@ struct Cli {
 fn main() {
     let cli = Cli::parse();
     let result = match cli.command {
+        Command::Hello(args) => {
+            let hello = Message::Hello(args.into());
+            serde_json::to_string(&hello)
+        }
         Command::Entry(args) => {
             let entry: Entry = args.into();
             serde_json::to_string(&entry)
@@ -285,8 +402,8 @@ fn main() {
             let options: Options = args.into();
             serde_json::to_string(&options)
         }
-        Command::Stop => {
-            let stop = Message::Stop;
+        Command::Stop(args) => {
+            let stop = Message::Stop { id: args.id };
             serde_json::to_string(&stop)
         }
     };
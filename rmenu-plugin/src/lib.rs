@@ -1,6 +1,14 @@
 //! RMenu-Plugin Object Implementations
 use serde::{Deserialize, Serialize};
 
+pub mod live;
+
+/// Current Version of the RMenu Plugin Protocol
+pub static PROTOCOL_VERSION: u32 = 1;
+
+/// Environment Variable used by RMenu to Advertise its Protocol Version
+pub static PROTOCOL_ENV: &str = "RMENU_PROTOCOL_VERSION";
+
 /// Methods allowed to Execute Actions on Selection
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -48,6 +56,22 @@ impl Action {
     }
 }
 
+/// Additional Content Displayed for Selected Entry
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preview {
+    /// plain text rendered as-is
+    Text(String),
+    /// inline html content
+    Html(String),
+    /// path to an image file
+    Image(String),
+    /// shell command whose output is rendered on selection
+    ///
+    /// Runs through `sh -c`, so paths and other arguments must be quoted.
+    Command(String),
+}
+
 /// RMenu Menu-Entry Implementation
 #[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
 #[serde(tag = "type", rename = "entry")]
@@ -60,6 +84,8 @@ pub struct Entry {
     pub icon: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub icon_alt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<Preview>,
 }
 
 impl Entry {
@@ -71,6 +97,7 @@ impl Entry {
             comment: comment.map(|c| c.to_owned()),
             icon: Default::default(),
             icon_alt: Default::default(),
+            preview: Default::default(),
         }
     }
     /// Generate a simplified Echo Action Entry
@@ -81,6 +108,7 @@ impl Entry {
             comment: comment.map(|c| c.to_owned()),
             icon: Default::default(),
             icon_alt: Default::default(),
+            preview: Default::default(),
         }
     }
 }
@@ -105,9 +133,17 @@ pub struct Options {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub context_menu: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub multi_select: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modal: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_results: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub use_icons: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub use_comments: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_preview: Option<bool>,
 
     // search settings
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -116,6 +152,8 @@ pub struct Options {
     pub search_restrict: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub search_max_length: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_mode: Option<String>,
     // key settings
     #[serde(skip_serializing_if = "Option::is_none")]
     pub key_exec: Option<Vec<String>>,
@@ -137,6 +175,12 @@ pub struct Options {
     pub key_mode_next: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub key_mode_prev: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_toggle_preview: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_toggle_mark: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_normal_mode: Option<Vec<String>>,
     // window settings
     #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
@@ -159,15 +203,100 @@ pub struct Search {
     pub is_regex: bool,
     #[serde(default)]
     pub ignore_case: bool,
+    /// Request ID that plugins may echo back in their replies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+}
+
+/// Plugin Capabilities Declared at Startup
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hello {
+    /// protocol version spoken by the plugin
+    pub version: u32,
+    /// plugin replies to every search sent over stdin
+    pub live: bool,
+    /// plugin entries include icons
+    pub icons: bool,
+    /// plugin entries include previews
+    pub preview: bool,
+}
+
+impl Hello {
+    /// Generate Capabilities for the Current Protocol Version
+    pub fn new(live: bool) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            live,
+            ..Default::default()
+        }
+    }
 }
 
 /// Valid RMenu Plugin Messages
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum Message {
+    Hello(Hello),
     Entry(Entry),
-    Options(Options),
-    Stop,
+    Options(Box<Options>),
+    /// Group of Entries in Reply to the Specified Search
+    Batch {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+        entries: Vec<Entry>,
+    },
+    /// End of Reply to the Specified (or Latest) Search
+    Stop {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+    },
+}
+
+impl Message {
+    /// Generate Stop Message in Reply to the Specified Search
+    ///
+    /// Replaces the former unit `Message::Stop` variant.
+    pub fn stop(search: Option<&Search>) -> Self {
+        Self::Stop {
+            id: search.and_then(|s| s.id),
+        }
+    }
+    /// Retrieve Search Request ID the Message Replies to (if Any)
+    pub fn id(&self) -> Option<u64> {
+        match self {
+            Self::Batch { id, .. } | Self::Stop { id } => *id,
+            _ => None,
+        }
+    }
+}
+
+/// In-Process RMenu Plugin Implementation
+///
+/// Built-in sources implement this trait so they may be compiled directly
+/// into rmenu rather than spawned as a separate process.
+pub trait Plugin: Send + Sync {
+    /// Plugin Capabilities (non-live plugins are searched in memory)
+    fn hello(&self) -> Hello {
+        Hello::new(false)
+    }
+    /// Prepare Plugin before its First Search
+    fn start(&mut self) {}
+    /// Collect Entries for the Specified Search
+    ///
+    /// Non-live plugins are only searched once and should return
+    /// every available entry.
+    fn search(&mut self, search: &Search) -> Vec<Entry>;
+    /// Release Plugin Resources when RMenu Exits
+    fn stop(&mut self) {}
+}
+
+/// Retrieve Protocol Version Advertised by RMenu (if Any)
+///
+/// Plugins should only send a `Hello` message when this is set, as older
+/// versions of rmenu do not understand the handshake.
+pub fn protocol_version() -> Option<u32> {
+    std::env::var(PROTOCOL_ENV).ok()?.parse().ok()
 }
 
 /// Retrieve EXE of Self
@@ -0,0 +1,304 @@
+//! Live-Search Plugin Helpers
+//!
+//! Implements the stdin/stdout side of the plugin protocol so live plugins
+//! only need to provide a single search callback. Readers and writers are
+//! generic so plugins may be exercised against in-memory streams.
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::{Entry, Hello, Message, Search};
+
+/// Maximum Number of Entries Buffered before Writing a Batch
+pub static BATCH_SIZE: usize = 50;
+
+/// Maximum Time Entries are Buffered before Writing a Batch
+pub static BATCH_DELAY: Duration = Duration::from_millis(50);
+
+/// Shared Writer for Complete Output Lines
+type Output = Arc<dyn Fn(&[u8]) -> std::io::Result<()> + Send + Sync>;
+
+/// Write Message as a Single Line and Flush Output
+fn write_message<T: Serialize>(output: &Output, message: &T) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    output(&line)
+}
+
+/// Buffered Reply to a Single Search
+///
+/// Replies are cheap to clone so they may be shared across worker threads.
+/// Entries sent after the search is cancelled are discarded.
+#[derive(Clone)]
+pub struct Reply {
+    search: Arc<Search>,
+    batch: bool,
+    cancel: Arc<AtomicBool>,
+    buffer: Arc<Mutex<(Vec<Entry>, Instant)>>,
+    output: Output,
+}
+
+impl Reply {
+    fn new(search: Search, batch: bool, output: Output) -> Self {
+        Self {
+            search: Arc::new(search),
+            batch,
+            cancel: Default::default(),
+            buffer: Arc::new(Mutex::new((vec![], Instant::now()))),
+            output,
+        }
+    }
+
+    /// Search being Replied To
+    #[inline]
+    pub fn search(&self) -> &Search {
+        &self.search
+    }
+
+    /// Check if a Newer Search has Replaced this One
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    /// Queue Entry for Output (Returns False once Cancelled)
+    pub fn send(&self, entry: Entry) -> bool {
+        if self.is_cancelled() {
+            return false;
+        }
+        let mut buffer = self.buffer.lock().expect("buffer lock poisoned");
+        buffer.0.push(entry);
+        if buffer.0.len() >= BATCH_SIZE || buffer.1.elapsed() >= BATCH_DELAY {
+            let entries = std::mem::take(&mut buffer.0);
+            buffer.1 = Instant::now();
+            drop(buffer);
+            if let Err(err) = self.write(entries) {
+                log::error!("failed to write entries: {err:?}");
+            }
+        }
+        true
+    }
+
+    /// Write Buffered Entries Immediately
+    pub fn flush(&self) -> std::io::Result<()> {
+        let mut buffer = self.buffer.lock().expect("buffer lock poisoned");
+        let entries = std::mem::take(&mut buffer.0);
+        buffer.1 = Instant::now();
+        drop(buffer);
+        self.write(entries)
+    }
+
+    fn write(&self, entries: Vec<Entry>) -> std::io::Result<()> {
+        if entries.is_empty() || self.is_cancelled() {
+            return Ok(());
+        }
+        if self.batch {
+            let id = self.search.id;
+            return write_message(&self.output, &Message::Batch { id, entries });
+        }
+        for entry in entries {
+            write_message(&self.output, &entry)?;
+        }
+        Ok(())
+    }
+
+    /// Flush Remaining Entries and Mark the Reply Finished
+    fn finish(&self) -> std::io::Result<()> {
+        self.flush()?;
+        write_message(&self.output, &Message::stop(Some(&self.search)))
+    }
+}
+
+/// Read Next Search from Input (None when Input is Closed)
+pub fn read_search<R: BufRead>(reader: &mut R) -> Option<Search> {
+    loop {
+        let mut buf = String::new();
+        match reader.read_line(&mut buf) {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(err) => {
+                log::error!("failed to read stdin: {err:?}");
+                return None;
+            }
+        }
+        let buf = buf.trim();
+        if buf.is_empty() {
+            continue;
+        }
+        match serde_json::from_str(buf) {
+            Ok(search) => return Some(search),
+            Err(err) => log::error!("invalid search message: {err:?}"),
+        }
+    }
+}
+
+/// Serve Live Searches from the Reader until it is Closed
+///
+/// Each search runs the handler on its own thread and cancels the search
+/// before it. A `Stop` message is written once the handler returns. The
+/// handshake is only written when `hello` is given, and entries are sent
+/// in batches only when a handshake is used.
+///
+/// The writer is returned once every search has finished.
+pub fn serve<R, W, F>(
+    hello: Option<Hello>,
+    mut reader: R,
+    writer: W,
+    handler: F,
+) -> std::io::Result<W>
+where
+    R: BufRead,
+    W: Write + Send + 'static,
+    F: Fn(&Reply) + Send + Sync + 'static,
+{
+    let writer = Arc::new(Mutex::new(writer));
+    let shared = Arc::clone(&writer);
+    let output: Output = Arc::new(move |line: &[u8]| {
+        let mut writer = shared.lock().expect("output lock poisoned");
+        writer.write_all(line)?;
+        writer.flush()
+    });
+    let batch = hello.is_some();
+    if let Some(hello) = hello {
+        write_message(&output, &Message::Hello(hello))?;
+    }
+    let handler = Arc::new(handler);
+    let mut current: Option<Reply> = None;
+    let mut threads: Vec<JoinHandle<()>> = vec![];
+    while let Some(search) = read_search(&mut reader) {
+        if let Some(reply) = current.take() {
+            reply.cancel.store(true, Ordering::Relaxed);
+        }
+        threads.retain(|t| !t.is_finished());
+        let reply = Reply::new(search, batch, Arc::clone(&output));
+        let handler = Arc::clone(&handler);
+        current = Some(reply.clone());
+        threads.push(std::thread::spawn(move || {
+            handler(&reply);
+            if let Err(err) = reply.finish() {
+                log::error!("failed to finish reply: {err:?}");
+            }
+        }));
+    }
+    log::debug!("input closed. waiting on {} searches", threads.len());
+    drop((current, output));
+    for thread in threads {
+        let _ = thread.join();
+    }
+    Arc::try_unwrap(writer)
+        .map(|w| w.into_inner().expect("output lock poisoned"))
+        .map_err(|_| std::io::Error::other("output still in use"))
+}
+
+/// Serve Live Searches over Stdin/Stdout
+///
+/// The handshake is only sent when rmenu advertises its protocol version.
+pub fn run<F>(hello: Hello, handler: F) -> std::io::Result<()>
+where
+    F: Fn(&Reply) + Send + Sync + 'static,
+{
+    let hello = crate::protocol_version().map(|_| hello);
+    let stdin = std::io::stdin().lock();
+    serve(hello, stdin, std::io::stdout(), handler)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    /// Serve Searches from In-Memory Input and Parse the Written Messages
+    fn serve_lines<F>(hello: Option<Hello>, searches: &[Value], handler: F) -> Vec<Value>
+    where
+        F: Fn(&Reply) + Send + Sync + 'static,
+    {
+        let input: String = searches.iter().map(|s| format!("{s}\n")).collect();
+        let output = serve(hello, Cursor::new(input), Vec::new(), handler).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect()
+    }
+
+    fn echo(reply: &Reply) {
+        let search = reply.search().search.to_owned();
+        reply.send(Entry::echo(&search, None));
+    }
+
+    #[test]
+    fn no_hello_without_handshake() {
+        let output = serve_lines(None, &[json!({"search": "a"})], echo);
+        assert_eq!(output.len(), 2);
+        assert_eq!(output[0]["type"], "entry");
+        assert_eq!(output[0]["name"], "a");
+        assert_eq!(output[1]["type"], "stop");
+    }
+
+    #[test]
+    fn hello_and_batches_with_handshake() {
+        let searches = [json!({"search": "a", "id": 1})];
+        let output = serve_lines(Some(Hello::new(true)), &searches, echo);
+        assert_eq!(output.len(), 3);
+        assert_eq!(output[0]["type"], "hello");
+        assert_eq!(output[0]["live"], true);
+        assert_eq!(output[1]["type"], "batch");
+        assert_eq!(output[1]["id"], 1);
+        assert_eq!(output[1]["entries"][0]["name"], "a");
+        assert_eq!(output[2], json!({"type": "stop", "id": 1}));
+    }
+
+    #[test]
+    fn stop_sent_for_every_search() {
+        let searches = [
+            json!({"search": "a", "id": 1}),
+            json!({"search": "b", "id": 2}),
+        ];
+        let output = serve_lines(Some(Hello::new(true)), &searches, |_| {});
+        let mut stops: Vec<u64> = output
+            .iter()
+            .filter(|m| m["type"] == "stop")
+            .map(|m| m["id"].as_u64().unwrap())
+            .collect();
+        stops.sort();
+        assert_eq!(stops, vec![1, 2]);
+    }
+
+    #[test]
+    fn cancelled_search_stops_writing() {
+        let searches = [
+            json!({"search": "old", "id": 1}),
+            json!({"search": "new", "id": 2}),
+        ];
+        let rejected = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&rejected);
+        let output = serve_lines(Some(Hello::new(true)), &searches, move |reply| {
+            if reply.search().id == Some(1) {
+                let start = Instant::now();
+                while !reply.is_cancelled() && start.elapsed() < Duration::from_secs(5) {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+                let sent = reply.send(Entry::echo("old", None));
+                flag.store(!sent, Ordering::Relaxed);
+                return;
+            }
+            echo(reply);
+        });
+        assert!(rejected.load(Ordering::Relaxed));
+        let names: Vec<&Value> = output
+            .iter()
+            .filter(|m| m["type"] == "batch")
+            .flat_map(|m| m["entries"].as_array().unwrap())
+            .map(|e| &e["name"])
+            .collect();
+        assert_eq!(names, vec!["new"]);
+    }
+}
@@ -0,0 +1,31 @@
+//! In-Process Plugins Compiled into RMenu
+use rmenu_plugin::Plugin;
+
+/// Names of Plugins Compiled into this Build
+pub static BUILTINS: &[&str] = &[
+    #[cfg(feature = "builtin-desktop")]
+    "desktop",
+    #[cfg(feature = "builtin-emoji")]
+    "emoji",
+    #[cfg(feature = "builtin-run")]
+    "run",
+];
+
+/// Retrieve Built-In Plugin by Name (if Compiled In)
+pub fn get(name: &str) -> Option<Box<dyn Plugin>> {
+    match name {
+        #[cfg(feature = "builtin-desktop")]
+        "desktop" => Some(Box::new(desktop::Desktop::default())),
+        #[cfg(feature = "builtin-emoji")]
+        "emoji" => Some(Box::new(emoji::Emoji::default())),
+        #[cfg(feature = "builtin-run")]
+        "run" => Some(Box::new(run::Run::default())),
+        _ => None,
+    }
+}
+
+/// Check if the Specified Plugin is Compiled In
+#[inline]
+pub fn exists(name: &str) -> bool {
+    BUILTINS.contains(&name)
+}
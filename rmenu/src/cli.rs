@@ -1,17 +1,29 @@
 ///! CLI Argument Based Configuration and Application Setup
+use std::collections::HashMap;
 use std::fs::read_to_string;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
-use crate::config::{cfg_replace, Config, Format, Keybind};
+use crate::config::{cfg_replace, Config, Format, Keybind, SearchMode};
+use crate::daemon::ShowRequest;
 use crate::server::{RMenuError, Result};
 use crate::{DEFAULT_CONFIG, DEFAULT_THEME, ENV_ACTIVE_PLUGINS, XDG_PREFIX};
 
+/// Additional RMenu Commands
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Show window of running rmenu daemon
+    Show,
+}
+
 /// Dynamic Applicaiton-Menu Tool (Built with Rust)
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     // simple configuration arguments
     /// Filepath for entry input
     #[arg(short, long)]
@@ -20,11 +32,17 @@ pub struct Args {
     #[arg(short, long, default_value_t=Format::Json)]
     pub format: Format,
     /// Plugins to run
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub run: Vec<String>,
     /// Limit which plugins are active
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub show: Vec<String>,
+    /// Run as persistent daemon w/ hidden window
+    #[arg(long)]
+    pub daemon: bool,
+    /// Reload config and css automatically when modified
+    #[arg(long)]
+    pub watch: bool,
     /// Override default configuration path
     #[arg(short, long, env = "RMENU_CONFIG")]
     config: Option<String>,
@@ -34,6 +52,18 @@ pub struct Args {
     /// Include additional css settings
     #[arg(long, env = "RMENU_CSS")]
     pub css: Option<String>,
+    /// Clear selection history of specified plugins (or all) and exit
+    #[arg(long)]
+    pub clear_history: bool,
+    /// List configured and discovered plugins and exit
+    #[arg(long)]
+    pub list_plugins: bool,
+    /// Print selected entries in the given format instead of executing them
+    #[arg(long)]
+    pub print_selection: Option<Format>,
+    /// Write plugin stderr to log files within the xdg state directory
+    #[arg(long)]
+    pub debug_plugins: bool,
 
     // root config settings
     /// Override terminal command
@@ -51,6 +81,9 @@ pub struct Args {
     /// Force enable/disable comments
     #[arg(long)]
     use_comments: Option<bool>,
+    /// Force enable/disable preview panel
+    #[arg(long)]
+    use_preview: Option<bool>,
     /// Allow Selection by Mouse Hover
     #[arg(long)]
     hover_select: Option<bool>,
@@ -60,6 +93,15 @@ pub struct Args {
     /// Allow Right Click Context Menu
     #[arg(long)]
     context_menu: Option<bool>,
+    /// Allow Marking Multiple Entries for Execution
+    #[arg(long)]
+    multi_select: Option<bool>,
+    /// Allow Vim-Style Normal Mode for Navigation
+    #[arg(long)]
+    modal: Option<bool>,
+    /// Group Results by Plugin rather than Interleaving by Score
+    #[arg(long)]
+    group_results: Option<bool>,
 
     // search settings
     /// Enforce Regex Pattern on Search
@@ -71,6 +113,12 @@ pub struct Args {
     /// Enforce Maximum Length on Search
     #[arg(long)]
     search_max_length: Option<usize>,
+    /// Override search matching mode
+    #[arg(long)]
+    search_mode: Option<SearchMode>,
+    /// Override delay (ms) before searching after input
+    #[arg(long)]
+    search_debounce: Option<u64>,
     /// Force enable/disable regex in search
     #[arg(long)]
     search_regex: Option<bool>,
@@ -112,6 +160,15 @@ pub struct Args {
     /// Override prev plugin keybind
     #[arg(long)]
     key_mode_prev: Option<Vec<Keybind>>,
+    /// Override toggle-preview keybind
+    #[arg(long)]
+    key_toggle_preview: Option<Vec<Keybind>>,
+    /// Override toggle-mark keybind
+    #[arg(long)]
+    key_toggle_mark: Option<Vec<Keybind>>,
+    /// Override enter-normal-mode keybind
+    #[arg(long)]
+    key_normal_mode: Option<Vec<Keybind>>,
 
     //window settings
     /// Override Window Title
@@ -145,16 +202,50 @@ impl Args {
     fn find_xdg_file(&self, name: &str, base: &Option<String>) -> Option<String> {
         return base.clone().or_else(|| {
             xdg::BaseDirectories::with_prefix(XDG_PREFIX)
-                .expect("Failed to read xdg base dirs")
+                .ok()?
                 .find_config_file(name)
                 .map(|f| f.to_string_lossy().to_string())
         });
     }
 
+    /// Plugins to Load (All Configured Plugins for Daemon by Default)
+    pub fn plugins(&self, config: &Config) -> Vec<String> {
+        match self.daemon && self.run.is_empty() && self.input.is_none() {
+            true => config.plugins.keys().cloned().collect(),
+            false => self.run.clone(),
+        }
+    }
+
+    /// Reject Arguments that cannot be Honored by the Daemon
+    ///
+    /// Selections are printed by the daemon process rather than the `show`
+    /// client, so printing them is only supported without the daemon.
+    pub fn check_daemon(&self) -> Result<()> {
+        let show = matches!(self.command, Some(Command::Show));
+        if self.print_selection.is_some() && (self.daemon || show) {
+            return Err(RMenuError::InvalidArgs(
+                "--print-selection is not supported in daemon mode".to_owned(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Build Daemon Show Request from Plugin Arguments
+    pub fn show_request(&self) -> ShowRequest {
+        ShowRequest {
+            run: self.run.clone(),
+            show: self.show.clone(),
+        }
+    }
+
+    /// Resolved Configuration Filepath (if Any)
+    pub fn config_path(&self) -> Option<String> {
+        self.find_xdg_file(DEFAULT_CONFIG, &self.config)
+    }
+
     /// Load Configuration File
-    pub fn get_config(&self) -> Result<Config> {
-        let config = self.find_xdg_file(DEFAULT_CONFIG, &self.config);
-        if let Some(path) = config {
+    fn load_config(&self) -> Result<Config> {
+        if let Some(path) = self.config_path() {
             log::debug!("loading config: {path:?}");
             let config: Config = match read_to_string(path) {
                 Ok(content) => serde_yaml::from_str(&content),
@@ -169,6 +260,34 @@ impl Args {
         Ok(Config::default())
     }
 
+    /// Load Configuration File and Merge Discovered Plugin Manifests
+    ///
+    /// Plugins configured directly within the config take precedence.
+    pub fn get_config(&self) -> Result<Config> {
+        let mut config = self.load_config()?;
+        for (name, plugin) in crate::manifest::discover()? {
+            config.plugins.entry(name).or_insert(plugin);
+        }
+        Ok(config)
+    }
+
+    /// Print Available Plugins and where they were Configured
+    pub fn list_plugins(&self, config: &Config) -> Result<()> {
+        let path = self.config_path().unwrap_or_default();
+        for (name, plugin) in config.plugins.iter() {
+            let source = match plugin.builtin.as_deref() {
+                Some(builtin) if crate::builtin::exists(builtin) => format!("builtin:{builtin}"),
+                _ => plugin
+                    .manifest
+                    .as_ref()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.to_owned()),
+            };
+            println!("{name}\t{source}");
+        }
+        Ok(())
+    }
+
     /// Update Configuration w/ CLI Specified Settings
     pub fn update_config(&self, mut config: Config) -> Config {
         // override basic settings
@@ -177,12 +296,18 @@ impl Args {
         config.page_load = self.page_load.unwrap_or(config.page_load);
         config.use_icons = self.use_icons.unwrap_or(config.use_icons);
         config.use_comments = self.use_comments.unwrap_or(config.use_comments);
+        config.use_preview = self.use_preview.unwrap_or(config.use_preview);
         config.hover_select = self.hover_select.unwrap_or(config.hover_select);
         config.single_click = self.single_click.unwrap_or(config.single_click);
         config.context_menu = self.context_menu.unwrap_or(config.context_menu);
+        config.multi_select = self.multi_select.unwrap_or(config.multi_select);
+        config.modal = self.modal.unwrap_or(config.modal);
+        config.group_results = self.group_results.unwrap_or(config.group_results);
         // override search settings
         cfg_replace!(config.search.restrict, self.search_restrict);
         cfg_replace!(config.search.max_length, self.search_max_length, true);
+        cfg_replace!(config.search.mode, self.search_mode, true);
+        cfg_replace!(config.search.debounce_ms, self.search_debounce, true);
         cfg_replace!(config.search.use_regex, self.search_regex, true);
         cfg_replace!(config.search.ignore_case, self.ignore_case, true);
         cfg_replace!(config.search.placeholder, self.placeholder);
@@ -197,6 +322,13 @@ impl Args {
         cfg_replace!(config.keybinds.jump_prev, self.key_jump_prev, true);
         cfg_replace!(config.keybinds.mode_next, self.key_mode_next, true);
         cfg_replace!(config.keybinds.mode_prev, self.key_move_prev, true);
+        cfg_replace!(
+            config.keybinds.toggle_preview,
+            self.key_toggle_preview,
+            true
+        );
+        cfg_replace!(config.keybinds.toggle_mark, self.key_toggle_mark, true);
+        cfg_replace!(config.keybinds.normal_mode, self.key_normal_mode, true);
         // override window settings
         cfg_replace!(config.window.title, self.title, true);
         cfg_replace!(config.window.size.width, self.width, true);
@@ -209,6 +341,18 @@ impl Args {
         config
     }
 
+    /// Clear Selection History for Specified or all Configured Plugins
+    pub fn reset_history(&self, config: &Config) -> Result<()> {
+        let names: Vec<&String> = match self.run.is_empty() {
+            true => config.plugins.keys().collect(),
+            false => self.run.iter().collect(),
+        };
+        for name in names {
+            crate::history::clear_history(name)?;
+        }
+        Ok(())
+    }
+
     /// Load CSS Theme or Default
     pub fn get_theme(&self) -> Option<String> {
         self.find_xdg_file(DEFAULT_THEME, &self.theme)
@@ -223,15 +367,32 @@ impl Args {
         std::env::set_var(ENV_ACTIVE_PLUGINS, running);
     }
 
+    /// Build Config Snapshot for each Mode w/ its Plugin Options Applied
+    ///
+    /// CLI and environment settings still take precedence over plugin options.
+    pub fn mode_configs(
+        &self,
+        config: &Config,
+        modes: &[String],
+        env_plugins: &[String],
+    ) -> Result<HashMap<String, Config>> {
+        let mut configs = HashMap::new();
+        for mode in modes {
+            let mut cfg = config.clone();
+            if let Some(options) = config.plugins.get(mode).and_then(|p| p.options.as_ref()) {
+                cfg.update(options)
+                    .map_err(|e| RMenuError::InvalidKeybind(e))?;
+            }
+            let mut cfg = self.update_config(cfg);
+            self.load_env(&mut cfg, env_plugins)?;
+            configs.insert(mode.to_owned(), cfg);
+        }
+        Ok(configs)
+    }
+
     /// Load Settings from Environment Variables for Multi-Stage Execution
-    pub fn load_env(&mut self, config: &mut Config) -> Result<()> {
-        let env_plugins = std::env::var(ENV_ACTIVE_PLUGINS).unwrap_or_default();
-        let active_plugins: Vec<&str> = env_plugins
-            .split(",")
-            .map(|s| s.trim())
-            .filter(|s| !s.is_empty())
-            .collect();
-        for name in active_plugins {
+    pub fn load_env(&self, config: &mut Config, env_plugins: &[String]) -> Result<()> {
+        for name in env_plugins {
             // retrieve plugin configuration
             log::info!("reloading plugin configuration for {name:?}");
             let plugin = config
@@ -249,3 +410,16 @@ impl Args {
         Ok(())
     }
 }
+
+/// Plugins Active in Parent RMenu Instances for Multi-Stage Execution
+///
+/// Must be read before [`Args::set_env`] appends the current plugins.
+pub fn env_plugins() -> Vec<String> {
+    std::env::var(ENV_ACTIVE_PLUGINS)
+        .unwrap_or_default()
+        .split(",")
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_owned())
+        .collect()
+}
@@ -1,6 +1,7 @@
 ///! File Based Configuration for RMenu
 use std::collections::BTreeMap;
 use std::fmt::Display;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 use rmenu_plugin::Options;
@@ -15,7 +16,7 @@ fn _true() -> bool {
 }
 
 /// Global RMenu Complete Configuration
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct Config {
     pub css: Option<String>,
@@ -27,9 +28,14 @@ pub struct Config {
     pub use_icons: bool,
     #[serde(default = "_true")]
     pub use_comments: bool,
+    #[serde(default = "_true")]
+    pub use_preview: bool,
     pub hover_select: bool,
     pub single_click: bool,
     pub context_menu: bool,
+    pub multi_select: bool,
+    pub modal: bool,
+    pub group_results: bool,
     pub search: SearchConfig,
     pub window: WindowConfig,
     pub keybinds: KeyConfig,
@@ -46,9 +52,13 @@ impl Default for Config {
             jump_dist: 5,
             use_icons: true,
             use_comments: true,
+            use_preview: true,
             hover_select: false,
             single_click: false,
             context_menu: false,
+            multi_select: false,
+            modal: false,
+            group_results: false,
             search: Default::default(),
             window: Default::default(),
             keybinds: Default::default(),
@@ -67,12 +77,19 @@ impl Config {
         cfg_replace!(self.hover_select, options.hover_select, true);
         cfg_replace!(self.single_click, options.single_click, true);
         cfg_replace!(self.context_menu, options.context_menu, true);
+        cfg_replace!(self.multi_select, options.multi_select, true);
+        cfg_replace!(self.modal, options.modal, true);
+        cfg_replace!(self.group_results, options.group_results, true);
         cfg_replace!(self.use_icons, options.use_icons, true);
         cfg_replace!(self.use_comments, options.use_comments, true);
+        cfg_replace!(self.use_preview, options.use_preview, true);
         // search settings
         cfg_replace!(self.search.placeholder, options.placeholder);
         cfg_replace!(self.search.restrict, options.search_restrict);
         cfg_replace!(self.search.max_length, options.search_max_length, true);
+        if let Some(mode) = options.search_mode.as_ref() {
+            self.search.mode = SearchMode::from_str(mode)?;
+        }
         // keybind settings
         cfg_keybind!(self.keybinds.exec, options.key_exec);
         cfg_keybind!(self.keybinds.exit, options.key_exit);
@@ -84,6 +101,9 @@ impl Config {
         cfg_keybind!(self.keybinds.jump_prev, options.key_jump_prev);
         cfg_keybind!(self.keybinds.mode_next, options.key_mode_next);
         cfg_keybind!(self.keybinds.mode_prev, options.key_mode_prev);
+        cfg_keybind!(self.keybinds.toggle_preview, options.key_toggle_preview);
+        cfg_keybind!(self.keybinds.toggle_mark, options.key_toggle_mark);
+        cfg_keybind!(self.keybinds.normal_mode, options.key_normal_mode);
         // window settings
         cfg_replace!(self.window.title, options.title, true);
         cfg_replace!(self.window.decorate, options.decorate, true);
@@ -100,14 +120,48 @@ fn _maxlen() -> usize {
     999
 }
 
+#[inline]
+fn _debounce() -> u64 {
+    50
+}
+
+/// Search Matching and Ranking Mode
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SearchMode {
+    #[default]
+    Standard,
+    Fuzzy,
+}
+
+impl Display for SearchMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!("{self:?}").to_lowercase())
+    }
+}
+
+impl FromStr for SearchMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "standard" | "default" => Ok(Self::Standard),
+            "fuzzy" => Ok(Self::Fuzzy),
+            _ => Err(format!("Invalid Search Mode: {s:?}")),
+        }
+    }
+}
+
 /// Search Configuration Settings
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 #[serde(default)]
 pub struct SearchConfig {
     pub restrict: Option<String>,
     #[serde(default = "_maxlen")]
     pub max_length: usize,
     pub placeholder: Option<String>,
+    pub mode: SearchMode,
+    #[serde(default = "_debounce")]
+    pub debounce_ms: u64,
     #[serde(default = "_true")]
     pub use_regex: bool,
     #[serde(default = "_true")]
@@ -120,13 +174,15 @@ impl Default for SearchConfig {
             restrict: Default::default(),
             max_length: 999,
             placeholder: Default::default(),
+            mode: Default::default(),
+            debounce_ms: 50,
             use_regex: true,
             ignore_case: true,
         }
     }
 }
 
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct WindowSize {
     pub width: f64,
     pub height: f64,
@@ -142,7 +198,7 @@ impl Default for WindowSize {
 }
 
 /// Window Configuration Settings
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct WindowConfig {
     pub title: String,
     pub size: WindowSize,
@@ -227,18 +283,74 @@ impl FromStr for CacheSetting {
     }
 }
 
+/// Restart Policy for Configured RMenu Plugins
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RestartPolicy {
+    /// restart whenever a live plugin exits or any plugin fails
+    Always,
+    #[default]
+    Never,
+    /// restart only when a plugin exits with an error or times out
+    OnFailure,
+}
+
+impl FromStr for RestartPolicy {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            "on-failure" | "onfailure" => Ok(Self::OnFailure),
+            _ => Err(format!("Invalid Restart Policy: {s:?}")),
+        }
+    }
+}
+
+#[inline]
+fn _max_restarts() -> usize {
+    3
+}
+
 /// RMenu Data-Source Plugin Configuration
 #[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct PluginConfig {
+    #[serde(default)]
     pub exec: Vec<String>,
+    /// in-process plugin used instead of `exec` when compiled into rmenu
+    #[serde(default)]
+    pub builtin: Option<String>,
     #[serde(default)]
     pub format: Format,
     #[serde(default)]
     pub cache: CacheSetting,
     #[serde(default)]
     pub placeholder: Option<String>,
+    /// name shown on the plugin mode tab
+    #[serde(default)]
+    pub label: Option<String>,
+    /// icon shown on the plugin mode tab
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// search prefix that routes queries to only this plugin
+    #[serde(default)]
+    pub trigger: Option<String>,
+    #[serde(default)]
+    pub history: bool,
     #[serde(default)]
     pub options: Option<Options>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub restart: RestartPolicy,
+    #[serde(default = "_max_restarts")]
+    pub max_restarts: usize,
+    /// manifest file the plugin was discovered from (if any)
+    #[serde(skip)]
+    pub manifest: Option<PathBuf>,
 }
 
 /// Allowed Formats for Entry Ingestion
@@ -268,7 +380,7 @@ impl FromStr for Format {
 }
 
 /// GUI Keybind Settings Options
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 #[serde(default)]
 pub struct KeyConfig {
     pub exec: Vec<Keybind>,
@@ -281,6 +393,37 @@ pub struct KeyConfig {
     pub jump_prev: Vec<Keybind>,
     pub mode_next: Vec<Keybind>,
     pub mode_prev: Vec<Keybind>,
+    pub toggle_preview: Vec<Keybind>,
+    pub toggle_mark: Vec<Keybind>,
+    pub normal_mode: Vec<Keybind>,
+    pub normal: NormalKeyConfig,
+    pub chord_timeout_ms: u64,
+    #[serde(deserialize_with = "de_custom")]
+    pub custom: Vec<(Keybind, KeyAction)>,
+}
+
+impl KeyConfig {
+    /// All Keybinds Available outside of Normal Mode
+    pub fn binds(&self) -> impl Iterator<Item = &Keybind> {
+        [
+            &self.exec,
+            &self.exit,
+            &self.move_next,
+            &self.move_prev,
+            &self.open_menu,
+            &self.close_menu,
+            &self.jump_next,
+            &self.jump_prev,
+            &self.mode_next,
+            &self.mode_prev,
+            &self.toggle_preview,
+            &self.toggle_mark,
+            &self.normal_mode,
+        ]
+        .into_iter()
+        .flatten()
+        .chain(self.custom.iter().map(|(bind, _)| bind))
+    }
 }
 
 impl Default for KeyConfig {
@@ -295,14 +438,127 @@ impl Default for KeyConfig {
             jump_next: vec![Keybind::new(Code::PageDown)],
             jump_prev: vec![Keybind::new(Code::PageUp)],
             mode_next: vec![Keybind::new(Code::Tab)],
-            mode_prev: vec![Keybind {
-                mods: Modifiers::SHIFT,
-                key: Code::Tab,
-            }],
+            mode_prev: vec![Keybind::with_mods(Modifiers::SHIFT, Code::Tab)],
+            toggle_preview: vec![Keybind::with_mods(Modifiers::CONTROL, Code::Slash)],
+            toggle_mark: vec![Keybind::with_mods(Modifiers::CONTROL, Code::Space)],
+            normal_mode: vec![Keybind::new(Code::Escape)],
+            normal: Default::default(),
+            chord_timeout_ms: 1000,
+            custom: vec![],
         };
     }
 }
 
+/// Normal (Modal) Mode Keybind Settings
+///
+/// Checked before the standard keybinds while normal mode is active.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct NormalKeyConfig {
+    pub exit: Vec<Keybind>,
+    pub insert: Vec<Keybind>,
+    pub move_next: Vec<Keybind>,
+    pub move_prev: Vec<Keybind>,
+    pub open_menu: Vec<Keybind>,
+    pub close_menu: Vec<Keybind>,
+    pub jump_next: Vec<Keybind>,
+    pub jump_prev: Vec<Keybind>,
+    pub first: Vec<Keybind>,
+    pub last: Vec<Keybind>,
+}
+
+impl NormalKeyConfig {
+    /// All Keybinds Specific to Normal Mode
+    pub fn binds(&self) -> impl Iterator<Item = &Keybind> {
+        [
+            &self.exit,
+            &self.insert,
+            &self.move_next,
+            &self.move_prev,
+            &self.open_menu,
+            &self.close_menu,
+            &self.jump_next,
+            &self.jump_prev,
+            &self.first,
+            &self.last,
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+impl Default for NormalKeyConfig {
+    fn default() -> Self {
+        Self {
+            exit: vec![Keybind::new(Code::KeyQ)],
+            insert: vec![Keybind::new(Code::KeyI), Keybind::new(Code::Slash)],
+            move_next: vec![Keybind::new(Code::KeyJ)],
+            move_prev: vec![Keybind::new(Code::KeyK)],
+            open_menu: vec![Keybind::new(Code::KeyL)],
+            close_menu: vec![Keybind::new(Code::KeyH)],
+            jump_next: vec![Keybind::with_mods(Modifiers::CONTROL, Code::KeyD)],
+            jump_prev: vec![Keybind::with_mods(Modifiers::CONTROL, Code::KeyU)],
+            first: vec![Keybind {
+                keys: vec![Keystroke::new(Code::KeyG); 2],
+            }],
+            last: vec![Keybind::with_mods(Modifiers::SHIFT, Code::KeyG)],
+        }
+    }
+}
+
+/// Custom Keybind Target as Written in Configuration
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyTarget {
+    action: Option<usize>,
+    name: Option<String>,
+    command: Option<String>,
+}
+
+impl TryFrom<KeyTarget> for KeyAction {
+    type Error = String;
+
+    fn try_from(target: KeyTarget) -> Result<Self, Self::Error> {
+        match (target.action, target.name, target.command) {
+            (Some(idx), None, None) => Ok(Self::Action(idx)),
+            (None, Some(name), None) => Ok(Self::Named(name)),
+            (None, None, Some(cmd)) => Ok(Self::Command(cmd)),
+            _ => Err(format!("Keybind requires one of: action, name, command")),
+        }
+    }
+}
+
+/// User-Defined Keybind Action Targets
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "KeyTarget")]
+pub enum KeyAction {
+    /// run action at the specified index on the selected entry
+    Action(usize),
+    /// run action with the specified name on the selected entry
+    Named(String),
+    /// run shell command templated with the selected entry's fields
+    Command(String),
+}
+
+// parse custom keybind table into keybind/action pairs (keeping config order)
+fn de_custom<'de, D>(deserializer: D) -> Result<Vec<(Keybind, KeyAction)>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let table: serde_yaml::Mapping = Deserialize::deserialize(deserializer)?;
+    table
+        .into_iter()
+        .map(|(bind, action)| {
+            let bind = bind
+                .as_str()
+                .ok_or_else(|| D::Error::custom("Keybind must be a string"))?;
+            let bind = Keybind::from_str(bind).map_err(D::Error::custom)?;
+            let action = serde_yaml::from_value(action).map_err(D::Error::custom)?;
+            Ok((bind, action))
+        })
+        .collect()
+}
+
 // parse supported modifiers from string
 fn mod_from_str(s: &str) -> Option<Modifiers> {
     match s.to_lowercase().as_str() {
@@ -314,23 +570,37 @@ fn mod_from_str(s: &str) -> Option<Modifiers> {
     }
 }
 
-/// Single GUI Keybind for Configuration
+/// Single Key Press with Modifiers
 #[derive(Debug, Clone, PartialEq)]
-pub struct Keybind {
+pub struct Keystroke {
     pub mods: Modifiers,
     pub key: Code,
 }
 
-impl Keybind {
+impl Keystroke {
     fn new(key: Code) -> Self {
         Self {
             mods: Modifiers::empty(),
             key,
         }
     }
+    /// Check if Key is a Modifier Pressed on its Own
+    pub fn is_modifier(&self) -> bool {
+        matches!(
+            self.key,
+            Code::AltLeft
+                | Code::AltRight
+                | Code::ControlLeft
+                | Code::ControlRight
+                | Code::ShiftLeft
+                | Code::ShiftRight
+                | Code::MetaLeft
+                | Code::MetaRight
+        )
+    }
 }
 
-impl FromStr for Keybind {
+impl FromStr for Keystroke {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -338,7 +608,12 @@ impl FromStr for Keybind {
         let mut mods = vec![];
         let mut keys = vec![];
         for item in s.split("+") {
-            let camel = format!("{}", heck::AsPascalCase(item));
+            // allow single letters/digits as shorthand for their key codes
+            let camel = match item.chars().collect::<Vec<_>>().as_slice() {
+                [c] if c.is_ascii_alphabetic() => format!("Key{}", c.to_ascii_uppercase()),
+                [c] if c.is_ascii_digit() => format!("Digit{c}"),
+                _ => format!("{}", heck::AsPascalCase(item)),
+            };
             match Code::from_str(&camel) {
                 Ok(key) => keys.push(key),
                 Err(_) => match mod_from_str(item) {
@@ -347,11 +622,11 @@ impl FromStr for Keybind {
                 },
             }
         }
-        // generate final keybind
+        // generate final keystroke
         let kmod = mods.into_iter().fold(Modifiers::empty(), |m1, m2| m1 | m2);
         match keys.len() {
             0 => Err(format!("No keys specified")),
-            1 => Ok(Keybind {
+            1 => Ok(Keystroke {
                 mods: kmod,
                 key: keys.pop().unwrap(),
             }),
@@ -360,6 +635,49 @@ impl FromStr for Keybind {
     }
 }
 
+/// Single GUI Keybind (Sequence of Keystrokes) for Configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keybind {
+    pub keys: Vec<Keystroke>,
+}
+
+impl Keybind {
+    fn new(key: Code) -> Self {
+        Self::with_mods(Modifiers::empty(), key)
+    }
+    fn with_mods(mods: Modifiers, key: Code) -> Self {
+        Self {
+            keys: vec![Keystroke { mods, key }],
+        }
+    }
+    /// Check if Keybind Exactly Matches the Pressed Keys
+    #[inline]
+    pub fn matches(&self, keys: &[Keystroke]) -> bool {
+        self.keys.as_slice() == keys
+    }
+    /// Check if the Pressed Keys are an Incomplete Start of the Keybind
+    #[inline]
+    pub fn is_prefix(&self, keys: &[Keystroke]) -> bool {
+        self.keys.len() > keys.len() && self.keys.starts_with(keys)
+    }
+}
+
+impl FromStr for Keybind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // sequences are written as whitespace separated keystrokes
+        let keys = s
+            .split_whitespace()
+            .map(Keystroke::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        match keys.is_empty() {
+            true => Err(format!("No keys specified")),
+            false => Ok(Self { keys }),
+        }
+    }
+}
+
 macro_rules! de_fromstr {
     ($s:ident) => {
         impl<'de> Deserialize<'de> for $s {
@@ -377,6 +695,8 @@ macro_rules! de_fromstr {
 // implement `Deserialize` using `FromStr`
 de_fromstr!(Format);
 de_fromstr!(CacheSetting);
+de_fromstr!(RestartPolicy);
+de_fromstr!(SearchMode);
 de_fromstr!(Keybind);
 
 macro_rules! cfg_replace {
@@ -408,3 +728,39 @@ macro_rules! cfg_keybind {
 pub(crate) use cfg_keybind;
 pub(crate) use cfg_replace;
 pub(crate) use de_fromstr;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keystroke_with_modifiers() {
+        let stroke = Keystroke::from_str("Ctrl+Shift+Enter").unwrap();
+        assert_eq!(stroke.mods, Modifiers::CONTROL | Modifiers::SHIFT);
+        assert_eq!(stroke.key, Code::Enter);
+    }
+
+    #[test]
+    fn keystroke_shorthand() {
+        assert_eq!(Keystroke::from_str("g").unwrap().key, Code::KeyG);
+        assert_eq!(Keystroke::from_str("5").unwrap().key, Code::Digit5);
+        assert_eq!(Keystroke::from_str("Arrow-Up").unwrap().key, Code::ArrowUp);
+    }
+
+    #[test]
+    fn keystroke_invalid() {
+        assert!(Keystroke::from_str("Ctrl+Nope").is_err());
+        assert!(Keystroke::from_str("Ctrl").is_err());
+        assert!(Keystroke::from_str("a+b").is_err());
+    }
+
+    #[test]
+    fn keybind_sequence() {
+        let bind = Keybind::from_str("Ctrl+x Ctrl+c").unwrap();
+        assert_eq!(bind.keys.len(), 2);
+        assert!(bind.is_prefix(&bind.keys[..1]));
+        assert!(!bind.is_prefix(&bind.keys));
+        assert!(bind.matches(&bind.keys));
+        assert!(Keybind::from_str("  ").is_err());
+    }
+}
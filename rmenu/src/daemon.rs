@@ -0,0 +1,98 @@
+//! Persistent Daemon Mode Unix-Socket Server and Client
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::server::{RMenuError, Result};
+use crate::XDG_PREFIX;
+
+static SOCKET_NAME: &'static str = "rmenu.sock";
+
+/// Request to Show Hidden Daemon Window w/ Specified Plugins
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ShowRequest {
+    pub run: Vec<String>,
+    pub show: Vec<String>,
+}
+
+impl ShowRequest {
+    /// Plugins to Activate when Showing Window
+    pub fn plugins(&self) -> Vec<String> {
+        match self.show.is_empty() {
+            true => self.run.clone(),
+            false => self.show.clone(),
+        }
+    }
+}
+
+/// Daemon Socket Path within XDG Runtime Directory
+fn socket_path() -> Result<PathBuf> {
+    Ok(xdg::BaseDirectories::with_prefix(XDG_PREFIX)?.place_runtime_file(SOCKET_NAME)?)
+}
+
+/// Send Show Request to Running RMenu Daemon
+pub fn send_show(request: &ShowRequest) -> Result<()> {
+    let path = socket_path()?;
+    log::debug!("connecting to daemon: {path:?}");
+    let mut stream = UnixStream::connect(&path)?;
+    let mut message = serde_json::to_vec(request)?;
+    message.push(b'\n');
+    stream.write_all(&message)?;
+    // wait for daemon acknowledgement
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    match reply.trim() {
+        "ok" => Ok(()),
+        err => Err(RMenuError::DaemonError(err.to_owned())),
+    }
+}
+
+/// Handle Single Client Connection and Forward Request
+fn handle_client(mut stream: UnixStream, sender: &UnboundedSender<ShowRequest>) -> Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let reply = match serde_json::from_str::<ShowRequest>(&line) {
+        Ok(request) => {
+            log::info!("daemon received show request: {request:?}");
+            match sender.send(request) {
+                Ok(_) => "ok".to_owned(),
+                Err(_) => "daemon gui unavailable".to_owned(),
+            }
+        }
+        Err(err) => format!("invalid request: {err}"),
+    };
+    stream.write_all(format!("{reply}\n").as_bytes())?;
+    Ok(())
+}
+
+/// Bind Daemon Socket and Forward Show Requests over Channel
+pub fn listen() -> Result<UnboundedReceiver<ShowRequest>> {
+    let path = socket_path()?;
+    if path.exists() {
+        if UnixStream::connect(&path).is_ok() {
+            return Err(RMenuError::DaemonError(format!(
+                "daemon already running at {path:?}"
+            )));
+        }
+        log::warn!("removing stale daemon socket {path:?}");
+        std::fs::remove_file(&path)?;
+    }
+    log::info!("daemon listening on {path:?}");
+    let listener = UnixListener::bind(&path)?;
+    let (sender, receiver) = unbounded_channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream
+                .map_err(RMenuError::from)
+                .and_then(|stream| handle_client(stream, &sender));
+            if let Err(err) = result {
+                log::error!("daemon client error: {err:?}");
+            }
+        }
+    });
+    Ok(receiver)
+}
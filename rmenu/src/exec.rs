@@ -1,14 +1,19 @@
 //! Execution Implementation for Entry Actions
+use std::io::Write;
 use std::process::Command;
 use std::{collections::HashMap, os::unix::process::CommandExt};
 
-use rmenu_plugin::{Action, Method};
-use shell_words::split;
+use rmenu_plugin::{Action, Entry, Method};
+use serde::Serialize;
+use shell_words::{quote, split};
 use strfmt::strfmt;
 use which::which;
 
+use crate::config::Format;
+use crate::server::{RMenuError, Result};
+
 /// Find Best Terminal To Execute
-fn find_terminal() -> String {
+fn find_terminal() -> Result<String> {
     vec![
         ("wezterm", "-e {cmd}"),
         ("alacritty", "-e {cmd}"),
@@ -18,46 +23,150 @@ fn find_terminal() -> String {
         ("xterm", "-C {cmd}"),
     ]
     .into_iter()
-    .map(|(t, v)| (which(t), v))
-    .filter(|(c, _)| c.is_ok())
-    .map(|(c, v)| (c.unwrap(), v))
-    .map(|(p, v)| {
-        (
-            p.to_str()
-                .expect("Failed to Parse Terminal Path")
-                .to_owned(),
-            v,
-        )
-    })
-    .find_map(|(p, v)| Some(format!("{p} {v}")))
-    .expect("Failed to Find Terminal Executable!")
+    .filter_map(|(t, v)| which(t).ok().map(|p| (p, v)))
+    .find_map(|(p, v)| p.to_str().map(|p| format!("{p} {v}")))
+    .ok_or(RMenuError::NoTerminal)
 }
 
 #[inline]
-fn parse_args(exec: &str) -> Vec<String> {
-    match split(exec) {
-        Ok(args) => args,
-        Err(err) => panic!("{:?} invalid command {err}", exec),
+fn parse_args(exec: &str) -> Result<Vec<String>> {
+    let args = split(exec).map_err(|err| RMenuError::InvalidCommand(format!("{exec:?} {err}")))?;
+    match args.is_empty() {
+        true => Err(RMenuError::InvalidCommand(format!("{exec:?} is empty"))),
+        false => Ok(args),
+    }
+}
+
+/// Build Action from Command Template using the Entry's Fields
+///
+/// Supports `{name}`, `{comment}`, `{icon}` and `{exec}` (the entry's main
+/// action command). Values are shell-quoted before substitution.
+pub fn template_action(template: &str, entry: &Entry) -> Option<Action> {
+    let exec = entry.actions.first().map(|a| match &a.exec {
+        Method::Terminal(exec) | Method::Run(exec) | Method::Echo(exec) => exec.to_owned(),
+    });
+    let mut args = HashMap::new();
+    args.insert("name".to_owned(), entry.name.to_owned());
+    args.insert(
+        "comment".to_owned(),
+        entry.comment.clone().unwrap_or_default(),
+    );
+    args.insert("icon".to_owned(), entry.icon.clone().unwrap_or_default());
+    args.insert("exec".to_owned(), exec.unwrap_or_default());
+    let args = args
+        .into_iter()
+        .map(|(k, v)| (k, quote(&v).to_string()))
+        .collect();
+    match strfmt(template, &args) {
+        Ok(command) => Some(Action {
+            name: "custom".to_owned(),
+            exec: Method::Run(command),
+            comment: None,
+        }),
+        Err(err) => {
+            log::error!("invalid command template {template:?}: {err:?}");
+            None
+        }
+    }
+}
+
+/// Selected Entry Printed in Place of Execution
+#[derive(Debug, Serialize)]
+struct Selection<'a> {
+    entry: &'a Entry,
+    action: Option<usize>,
+}
+
+/// Print Selected Entries and Action Indexes instead of Executing them
+///
+/// JSON output includes the full entry while dmenu output only lists
+/// the entry names, one selection per line.
+pub fn print_selection(format: Format, selected: Vec<(&Entry, Option<usize>)>) {
+    let mut stdout = std::io::stdout().lock();
+    for (entry, action) in selected {
+        let line = match format {
+            Format::DMenu => entry.name.to_owned(),
+            Format::Json => match serde_json::to_string(&Selection { entry, action }) {
+                Ok(json) => json,
+                Err(err) => {
+                    log::error!("failed to serialize selection: {err:?}");
+                    continue;
+                }
+            },
+        };
+        if let Err(err) = writeln!(stdout, "{line}") {
+            log::error!("failed to print selection: {err:?}");
+        }
     }
+    let _ = stdout.flush();
 }
 
-/// Execute the Entry Action as Specified
-pub fn execute(action: &Action, term: Option<String>) {
+/// Build Command for the Entry Action (Printing Echo Actions Directly)
+fn build_command(action: &Action, term: Option<String>) -> Result<Option<Command>> {
     log::info!("executing: {:?} {:?}", action.name, action.exec);
     let args = match &action.exec {
-        Method::Run(exec) => parse_args(&exec),
+        Method::Run(exec) => parse_args(&exec)?,
         Method::Terminal(exec) => {
             let mut args = HashMap::new();
-            let terminal = term.unwrap_or_else(find_terminal);
+            let terminal = match term {
+                Some(term) => term,
+                None => find_terminal()?,
+            };
             args.insert("cmd".to_string(), exec.to_owned());
-            let command = strfmt(&terminal, &args).expect("Failed String Format");
-            parse_args(&command)
+            let command = strfmt(&terminal, &args)
+                .map_err(|err| RMenuError::InvalidCommand(format!("{terminal:?} {err}")))?;
+            parse_args(&command)?
         }
         Method::Echo(echo) => {
             println!("{echo}");
-            return;
+            return Ok(None);
         }
     };
-    let err = Command::new(&args[0]).args(&args[1..]).exec();
-    panic!("Command Error: {err:?}");
+    let mut command = Command::new(&args[0]);
+    command.args(&args[1..]);
+    Ok(Some(command))
+}
+
+/// Build Commands for all Entry Actions before Executing any of them
+pub fn build_commands(actions: &[Action], term: Option<String>) -> Result<Vec<Command>> {
+    let mut commands = vec![];
+    for action in actions {
+        if let Some(command) = build_command(action, term.clone())? {
+            commands.push(command);
+        }
+    }
+    Ok(commands)
+}
+
+/// Execute the Entry Action Commands as Specified
+///
+/// A single command replaces the current process unless `spawn` is set, in
+/// which case it runs as a child process (used in daemon mode). Multiple
+/// commands run one after another, on a background thread when `spawn` is set.
+pub fn execute(mut commands: Vec<Command>, spawn: bool) -> Result<()> {
+    if commands.len() == 1 {
+        let mut command = commands.remove(0);
+        if spawn {
+            let mut child = command.spawn()?;
+            std::thread::spawn(move || child.wait());
+            return Ok(());
+        }
+        return Err(command.exec().into());
+    }
+    let run = move || {
+        for mut command in commands {
+            match command.status() {
+                Ok(status) if !status.success() => log::warn!("Command Failed: {status:?}"),
+                Ok(_) => {}
+                Err(err) => log::error!("Command Error: {err:?}"),
+            }
+        }
+    };
+    match spawn {
+        true => {
+            std::thread::spawn(run);
+        }
+        false => run(),
+    }
+    Ok(())
 }
@@ -0,0 +1,46 @@
+//! Process Exit Status Codes
+use std::fmt::Display;
+
+/// First Exit Code used for Custom Keybinds (Matching Rofi's `-kb-custom-N`)
+const CUSTOM_BASE: i32 = 10;
+
+/// Reason RMenu is Exiting
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Exit {
+    /// entry was selected (exit code 0)
+    Selected,
+    /// menu was closed without a selection (exit code 1)
+    #[default]
+    Cancelled,
+    /// unexpected runtime error (exit code 2)
+    Error,
+    /// configuration or cli arguments are invalid (exit code 3)
+    ConfigInvalid,
+    /// plugin failed to start or exited with an error (exit code 4)
+    PluginFailed,
+    /// no terminal available to run terminal actions (exit code 5)
+    NoTerminal,
+    /// custom keybind at the given index was used (exit code 10 + index)
+    Custom(usize),
+}
+
+impl Exit {
+    /// Process Exit Code for the Exit Reason
+    pub fn code(&self) -> i32 {
+        match self {
+            Self::Selected => 0,
+            Self::Cancelled => 1,
+            Self::Error => 2,
+            Self::ConfigInvalid => 3,
+            Self::PluginFailed => 4,
+            Self::NoTerminal => 5,
+            Self::Custom(idx) => CUSTOM_BASE + *idx as i32,
+        }
+    }
+}
+
+impl Display for Exit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?} ({})", self.code())
+    }
+}
@@ -1,18 +1,66 @@
+use std::collections::BTreeSet;
+use std::ops::Range;
 use std::sync::{Arc, RwLock};
 
 use dioxus::prelude::*;
+use rmenu_plugin::Preview;
 
 mod entry;
 mod image;
+mod preview;
 mod state;
 
 pub use state::ContextBuilder;
-use state::{Context, ContextMenu, Position};
+use state::{Context, ContextMenu, InputMode, ModeTab, Position};
+
+use crate::server::{Scored, Snapshot};
 
 const DEFAULT_CSS_CONTENT: &'static str = include_str!("../../public/default.css");
 
 type Ctx = Arc<RwLock<Context>>;
 
+/// Score Search Snapshot on Background Thread without Blocking the GUI
+async fn score(snapshot: Snapshot) -> Scored {
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(snapshot.score());
+    });
+    receiver.await.unwrap_or_else(|_| {
+        log::error!("search worker failed");
+        Scored::default()
+    })
+}
+
+/// Send Search and Publish its Results Scored on the Worker
+///
+/// Plugin events that arrived while scoring trigger another pass so the
+/// published results never lag behind the streamed entries.
+async fn flush_search(
+    ctx: Ctx,
+    search: &str,
+    mut position: Signal<Position>,
+    mut results: Signal<Vec<usize>>,
+) {
+    let snapshot = ctx
+        .write()
+        .expect("failed to write ctx")
+        .prepare_search(search);
+    let scored = score(snapshot).await;
+    let mut rescore = {
+        let mut context = ctx.write().expect("failed to write ctx");
+        results.set(context.publish_search(scored, &mut position));
+        context.rescore()
+    };
+    while let Some((generation, snapshot)) = rescore {
+        let scored = score(snapshot).await;
+        let mut context = ctx.write().expect("failed to write ctx");
+        if let Some(updated) = context.publish_events(generation, scored, &mut position) {
+            results.set(updated);
+        }
+        rescore = context.rescore();
+    }
+}
+
 pub fn run(ctx: Context) {
     let window = dioxus_desktop::WindowBuilder::default()
         .with_title(ctx.config.window.title.clone())
@@ -20,6 +68,7 @@ pub fn run(ctx: Context) {
         .with_decorations(ctx.config.window.decorate)
         .with_transparent(ctx.config.window.transparent)
         .with_always_on_top(ctx.config.window.always_top)
+        .with_visible(!ctx.daemon)
         .with_inner_size(ctx.config.window.logical_size())
         .with_fullscreen(ctx.config.window.get_fullscreen())
         .with_theme(ctx.config.window.get_theme());
@@ -43,10 +92,13 @@ fn gui_main() -> Element {
     let mut position = use_signal(Position::default);
     let mut results = use_signal(|| ctx.read().expect("failed to read ctx").all_results());
     let mut ctx_menu = use_signal(ContextMenu::default);
+    let mut styles = use_signal(|| 0u64);
 
-    // refocus on input
-    let js = format!("setTimeout(() => {{ document.getElementById('search').focus() }}, 100)");
-    document::eval(&js);
+    // refocus on input (unless navigating in normal mode)
+    if ctx.read().expect("failed to read ctx").mode == InputMode::Insert {
+        let js = format!("setTimeout(() => {{ document.getElementById('search').focus() }}, 100)");
+        document::eval(&js);
+    }
 
     // configure exit cleanup function
     use_drop(move || {
@@ -54,16 +106,114 @@ fn gui_main() -> Element {
         ctx.write().expect("failed to write ctx").cleanup();
     });
 
-    // update search results on search
+    // listen for show requests when running as daemon
+    let daemon_ctx = use_context::<Ctx>();
+    let daemon_window = dioxus_desktop::use_window();
+    use_hook(move || {
+        let requests = daemon_ctx
+            .write()
+            .expect("failed to write ctx")
+            .take_requests();
+        let Some(mut requests) = requests else {
+            return;
+        };
+        spawn(async move {
+            while let Some(request) = requests.recv().await {
+                search.set(String::new());
+                daemon_ctx.write().expect("failed to write ctx").show(
+                    &request,
+                    &mut position,
+                    &mut results,
+                );
+                daemon_window.set_visible(true);
+                daemon_window.set_focus();
+                document::eval("document.getElementById('search').focus()");
+            }
+        });
+    });
+
+    // apply config and css changes when watching for modifications
+    let reload_ctx = use_context::<Ctx>();
+    use_hook(move || {
+        let reloads = reload_ctx
+            .write()
+            .expect("failed to write ctx")
+            .take_reloads();
+        let Some(mut reloads) = reloads else {
+            return;
+        };
+        spawn(async move {
+            while let Some(reload) = reloads.recv().await {
+                reload_ctx.write().expect("failed to write ctx").reload(
+                    reload,
+                    &mut position,
+                    &mut results,
+                    &mut styles,
+                );
+            }
+        });
+    });
+
+    // refresh results as plugins stream their replies
+    let stream_ctx = use_context::<Ctx>();
+    use_hook(move || {
+        let events = stream_ctx
+            .write()
+            .expect("failed to write ctx")
+            .take_events();
+        let Some(mut events) = events else {
+            return;
+        };
+        spawn(async move {
+            while let Some(event) = events.recv().await {
+                // drain everything already available to render once per burst
+                let mut batch = vec![event];
+                while let Ok(event) = events.try_recv() {
+                    batch.push(event);
+                }
+                // apply under the lock but rescore on the search worker
+                let rescore = stream_ctx
+                    .write()
+                    .expect("failed to write ctx")
+                    .handle_events(batch);
+                let Some((generation, snapshot)) = rescore else {
+                    continue;
+                };
+                let scored = score(snapshot).await;
+                let mut context = stream_ctx.write().expect("failed to write ctx");
+                if let Some(updated) = context.publish_events(generation, scored, &mut position) {
+                    results.set(updated);
+                }
+            }
+        });
+    });
+
+    // dispatch debounced search task on search, cancelling any superseded task
     let effect_ctx = use_context::<Ctx>();
+    let mut searching = use_signal(|| false);
+    let mut pending = use_signal(|| None::<Task>);
     use_effect(move || {
         let search = search();
-        results.set(
-            effect_ctx
-                .write()
-                .expect("failed to write ctx")
-                .set_search(&search, &mut position),
-        );
+        if let Some(task) = pending.write().take() {
+            task.cancel();
+        }
+        let debounce = match search.is_empty() {
+            true => 0,
+            false => {
+                let context = effect_ctx.read().expect("failed to read ctx");
+                context.config.search.debounce_ms
+            }
+        };
+        let ctx = effect_ctx.clone();
+        searching.set(true);
+        let task = spawn(async move {
+            if debounce > 0 {
+                tokio::time::sleep(std::time::Duration::from_millis(debounce)).await;
+            }
+            flush_search(ctx, &search, position, results).await;
+            searching.set(false);
+        });
+        pending.set(Some(task));
     });
 
     // declare keyboard handler
@@ -71,18 +221,39 @@ fn gui_main() -> Element {
     let window = dioxus_desktop::use_window();
     let key_ctx = use_context::<Ctx>();
     let keydown = move |e: KeyboardEvent| {
-        let mut context = key_ctx.write().expect("failed to write ctx");
         // suport console key
         #[cfg(debug_assertions)]
         if e.code() == Code::Backquote {
             window.devtool();
             return;
         }
-        // calculate current entry index
-        let pos = position.with(|p| p.pos);
-        let index = results.with(|r| r.get(pos).cloned().unwrap_or(0));
-        // handle events
-        context.handle_keybinds(e, index, &mut position, &mut results);
+        let ctx = key_ctx.clone();
+        let mut handle = move |e: KeyboardEvent| {
+            // calculate current entry index
+            let pos = position.with(|p| p.pos);
+            let index = results.with(|r| r.get(pos).cloned().unwrap_or(0));
+            // handle events
+            let mut context = ctx.write().expect("failed to write ctx");
+            context.handle_keybinds(e, index, &mut position, &mut results);
+        };
+        // flush pending search on the worker before navigating or executing
+        let editing = matches!(e.key(), Key::Character(_) | Key::Backspace | Key::Delete);
+        if searching() && !editing {
+            if let Some(task) = pending.write().take() {
+                task.cancel();
+            }
+            // keybinds run after the flush, too late to cancel the default
+            e.prevent_default();
+            let ctx = key_ctx.clone();
+            let search = search.peek().clone();
+            spawn(async move {
+                flush_search(ctx, &search, position, results).await;
+                searching.set(false);
+                handle(e);
+            });
+            return;
+        }
+        handle(e);
     };
 
     // handle quit event
@@ -90,6 +261,10 @@ fn gui_main() -> Element {
     let context = ctx.read().expect("failed to read ctx");
     if context.quit {
         window.set_visible(false);
+    }
+    if context.quit && !context.daemon {
+        let exit = context.exit;
+        let exit_ctx = ctx.clone();
         spawn(async move {
             // wait for window to vanish
             let time = std::time::Duration::from_millis(50);
@@ -97,11 +272,23 @@ fn gui_main() -> Element {
             while window.is_visible() {
                 tokio::time::sleep(time).await;
             }
-            // actually close app after it becomes invisible
-            window.close();
+            // actually exit app after it becomes invisible
+            log::debug!("exiting: {exit}");
+            exit_ctx.write().expect("failed to write ctx").cleanup();
+            std::process::exit(exit.code());
         });
     }
 
+    // return to insert mode when search is focused directly (ex: mouse click)
+    let focus_ctx = use_context::<Ctx>();
+    let focus = move |_| {
+        let mut context = focus_ctx.write().expect("failed to write ctx");
+        if context.mode == InputMode::Normal {
+            context.mode = InputMode::Insert;
+            position.with_mut(|_| {});
+        }
+    };
+
     // prevent cursor from jumping within input on arrow up/down
     let disable_arrows = |e: KeyboardEvent| {
         let code = e.code();
@@ -113,6 +300,33 @@ fn gui_main() -> Element {
     let pattern = context.config.search.restrict.clone();
     let maxlength = context.config.search.max_length as i64;
     let max_result = context.calc_limit(&position);
+    let sclass = searching().then_some("searching").unwrap_or_default();
+    let pclass = context
+        .use_preview
+        .then_some("with-preview")
+        .unwrap_or_default();
+    let mode = context.mode;
+    let modes = context.modes();
+    let mclass = (modes.len() > 1)
+        .then_some("with-modes")
+        .unwrap_or_default();
+
+    // label the first result of each plugin when grouping results
+    let group = context.config.group_results;
+    let mut last = None;
+    let rows: Vec<(usize, usize, Option<String>)> = results()
+        .iter()
+        .take(max_result)
+        .enumerate()
+        .map(|(pos, index)| {
+            let source = context.get_source(*index);
+            let header = (group && last != Some(source)).then(|| source.to_owned());
+            last = Some(source);
+            (pos, *index, header)
+        })
+        .collect();
+    // re-render stylesheets when reloaded by the file watcher
+    let _ = styles();
     rsx! {
         style { "{DEFAULT_CSS_CONTENT}" }
         style { "{context.theme}" }
@@ -120,10 +334,11 @@ fn gui_main() -> Element {
         // menu content
         div {
             id: "body",
-            class: "body",
+            class: "body mode-{mode} {mclass}",
             div {
                 id: "content",
                 class: "content",
+                tabindex: "-1",
                 onclick: move |_| {
                     ctx_menu.with_mut(|m| m.reset());
                 },
@@ -139,21 +354,42 @@ fn gui_main() -> Element {
                         placeholder: "{context.placeholder}",
                         oninput: move |e| search.set(e.value()),
                         onkeydown: disable_arrows,
+                        onfocus: focus,
+                    }
+                    if modes.len() > 1 {
+                        div {
+                            id: "modes",
+                            class: "modes",
+                            for tab in modes {
+                                gui_mode { key: "{tab.name}", tab, position, results }
+                            }
+                        }
+                    }
+                }
+                if let Some(err) = context.error() {
+                    gui_error {
+                        position,
+                        message: err.to_string(),
+                        stderr: err.stderr().to_vec(),
                     }
                 }
                 div {
                     id: "results",
-                    class: "results",
-                    for (pos, index) in results().iter().take(max_result).enumerate() {
+                    class: "results {sclass} {pclass}",
+                    for (pos, index, header) in rows {
                         gui_entry {
                             key: "{pos}-{index}",
                             ctx_menu,
                             position,
                             search_index: pos,
-                            entry_index: *index,
+                            entry_index: index,
+                            header,
                         }
                     }
                 }
+                if context.use_preview {
+                    gui_preview { position, results }
+                }
             }
         }
         // custom context menu
@@ -167,11 +403,12 @@ struct Row {
     position: Signal<Position>,
     search_index: usize,
     entry_index: usize,
+    header: Option<String>,
 }
 
 impl PartialEq for Row {
     fn eq(&self, other: &Self) -> bool {
-        self.entry_index == other.entry_index
+        self.entry_index == other.entry_index && self.header == other.header
     }
 }
 
@@ -191,11 +428,65 @@ fn render_image(image: Option<&String>, alt: Option<&String>) -> Element {
     return rsx! { div { class: "icon_alt", dangerous_inner_html: "{alt}" } };
 }
 
+#[inline]
+fn render_preview(preview: Option<&Preview>) -> Element {
+    match preview {
+        None => rsx! {},
+        Some(Preview::Text(text)) => rsx! { pre { class: "preview-text", "{text}" } },
+        Some(Preview::Html(html)) => {
+            rsx! { div { class: "preview-html", dangerous_inner_html: "{html}" } }
+        }
+        Some(Preview::Image(path)) => {
+            let path = shellexpand::tilde(path).to_string();
+            let src = match path.ends_with(".svg") {
+                true => image::convert_svg(path.to_owned()).unwrap_or(path),
+                false => path,
+            };
+            rsx! { img { class: "preview-image", src: "{src}" } }
+        }
+        Some(Preview::Command(command)) => rsx! {
+            preview_command { key: "{command}", command: command.to_owned() }
+        },
+    }
+}
+
+/// Escape Text for HTML and Wrap Matched Character Ranges in Marks
+fn highlight(text: &str, ranges: &[Range<usize>]) -> String {
+    let mut html = String::with_capacity(text.len());
+    let mut ranges = ranges.iter().peekable();
+    for (idx, c) in text.chars().enumerate() {
+        if ranges.peek().is_some_and(|r| r.start == idx) {
+            html.push_str("<mark class=\"match\">");
+        }
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            _ => html.push(c),
+        }
+        if ranges.peek().is_some_and(|r| r.end == idx + 1) {
+            html.push_str("</mark>");
+            ranges.next();
+        }
+    }
+    html
+}
+
 fn gui_entry(mut row: Row) -> Element {
     // retrieve entry information based on index
     let ctx = use_context::<Ctx>();
     let context = ctx.read().expect("failed to read ctx");
-    let entry = context.get_entry(row.entry_index);
+    let Some(entry) = context.get_entry(row.entry_index) else {
+        return rsx! {};
+    };
+    let matches = context.get_matches(row.entry_index);
+    let name = highlight(&entry.name, &matches.name);
+    let comment = highlight(
+        entry.comment.as_ref().map(|s| s.as_str()).unwrap_or(""),
+        &matches.comment,
+    );
     let hover_select = context.config.hover_select;
     let (pos, subpos) = row.position.with(|p| (p.pos, p.subpos));
 
@@ -207,6 +498,11 @@ fn gui_entry(mut row: Row) -> Element {
     let rclass = (pos == row.search_index && subpos == 0)
         .then_some("selected")
         .unwrap_or_default();
+    let mclass = context
+        .is_marked(row.entry_index)
+        .then_some("marked")
+        .unwrap_or_default();
+    let source = heck::AsKebabCase(context.get_source(row.entry_index));
 
     // context menu event handler
     let contextmenu = move |e: Event<MouseData>| {
@@ -231,10 +527,9 @@ fn gui_entry(mut row: Row) -> Element {
         row.position.with_mut(|p| p.set(row.search_index, 0));
         if single_click && !menu_active {
             let mut pos = row.position.clone();
-            result_ctx1
-                .write()
-                .expect("failed to write ctx")
-                .execute(row.entry_index, &mut pos);
+            let mut context = result_ctx1.write().expect("failed to write ctx");
+            let selection = context.selection(row.entry_index);
+            context.execute(selection, &mut pos);
         }
     };
 
@@ -243,20 +538,22 @@ fn gui_entry(mut row: Row) -> Element {
     let doubleclick = move |_| {
         if !menu_active {
             let mut pos = row.position.clone();
-            result_ctx2
-                .write()
-                .expect("failed to write ctx")
-                .execute(row.entry_index, &mut pos);
+            let mut context = result_ctx2.write().expect("failed to write ctx");
+            let selection = context.selection(row.entry_index);
+            context.execute(selection, &mut pos);
         }
     };
 
     rsx! {
         div {
-            class: "result-entry",
+            class: "result-entry source-{source}",
+            if let Some(header) = row.header.as_ref() {
+                div { class: "group-header", "{header}" }
+            }
             // main-entry
             div {
                 id: "result-{row.search_index}",
-                class: "result {rclass}",
+                class: "result {rclass} {mclass}",
                 // actions
                 oncontextmenu: contextmenu,
                 onmouseenter: mouseenter,
@@ -275,18 +572,18 @@ fn gui_entry(mut row: Row) -> Element {
                     {rsx! {
                         div {
                             class: "name",
-                            dangerous_inner_html: "{entry.name}"
+                            dangerous_inner_html: "{name}"
                         }
                         div {
                             class: "comment",
-                            dangerous_inner_html: entry.comment.as_ref().map(|s| s.as_str()).unwrap_or(""),
+                            dangerous_inner_html: "{comment}",
                         }
                     }}
                 } else {
                     {rsx! {
                         div {
                             class: "entry",
-                            dangerous_inner_html: "{entry.name}"
+                            dangerous_inner_html: "{name}"
                         }
                     }}
                 }
@@ -318,12 +615,12 @@ fn gui_entry(mut row: Row) -> Element {
                             row.position.with_mut(|p| p.set(row.search_index, 0));
                             if single_click {
                                 let mut pos = row.position.clone();
-                                ctx.write().expect("failed to write ctx").execute(row.entry_index, &mut pos);
+                                ctx.write().expect("failed to write ctx").execute(BTreeSet::from([row.entry_index]), &mut pos);
                             }
                         },
                         ondoubleclick: move |_| {
                             let mut pos = row.position.clone();
-                            ctx2.write().expect("failed to write ctx").execute(row.entry_index, &mut pos);
+                            ctx2.write().expect("failed to write ctx").execute(BTreeSet::from([row.entry_index]), &mut pos);
                         },
                         // content
                         div {
@@ -341,12 +638,88 @@ fn gui_entry(mut row: Row) -> Element {
     }
 }
 
+#[component]
+fn gui_mode(tab: ModeTab, position: Signal<Position>, results: Signal<Vec<usize>>) -> Element {
+    let ctx = use_context::<Ctx>();
+    let aclass = tab.active.then_some("active").unwrap_or_default();
+    let name = tab.name.clone();
+    rsx! {
+        div {
+            class: "mode-tab {aclass}",
+            title: "{tab.name}",
+            onclick: move |_| {
+                ctx.write().expect("failed to write ctx").switch_mode(
+                    &name,
+                    &mut position,
+                    &mut results,
+                );
+            },
+            if tab.icon.is_some() {
+                div {
+                    class: "mode-icon",
+                    {render_image(tab.icon.as_ref(), None)}
+                }
+            }
+            div { class: "mode-label", "{tab.label}" }
+        }
+    }
+}
+
+#[component]
+fn gui_error(position: Signal<Position>, message: String, stderr: Vec<String>) -> Element {
+    let ctx = use_context::<Ctx>();
+    let stderr = stderr.join("\n");
+    rsx! {
+        div {
+            id: "error",
+            class: "error",
+            title: "Click to dismiss",
+            onclick: move |_| {
+                ctx.write().expect("failed to write ctx").clear_error(&mut position);
+            },
+            div { class: "error-message", "{message}" }
+            if !stderr.is_empty() {
+                pre { class: "error-stderr", "{stderr}" }
+            }
+        }
+    }
+}
+
+#[component]
+fn gui_preview(position: Signal<Position>, results: Signal<Vec<usize>>) -> Element {
+    let ctx = use_context::<Ctx>();
+    let context = ctx.read().expect("failed to read ctx");
+    let pos = position.with(|p| p.pos);
+    let index = results.with(|r| r.get(pos).cloned());
+    let preview = index.and_then(|i| context.get_entry(i)?.preview.as_ref());
+    rsx! {
+        div {
+            id: "preview",
+            class: "preview",
+            {render_preview(preview)}
+        }
+    }
+}
+
+#[component]
+fn preview_command(command: String) -> Element {
+    // run lazily once the entry is selected and render when complete
+    let output = use_resource(move || preview::run_command(command.clone()));
+    let text = output.read().clone().unwrap_or_default();
+    rsx! { pre { class: "preview-text", "{text}" } }
+}
+
 #[component]
 fn context_menu(ctx_menu: Signal<ContextMenu>, position: Signal<Position>) -> Element {
     let ctx = use_context::<Ctx>();
     let context = ctx.read().expect("failed to read ctx");
-    let index = ctx_menu.with(|c| c.entry.unwrap_or(0));
-    let entry = context.get_entry(index);
+    // entries may not have streamed in yet or been replaced since opening
+    let Some(index) = ctx_menu.with(|c| c.entry) else {
+        return rsx! {};
+    };
+    let Some(entry) = context.get_entry(index) else {
+        return rsx! {};
+    };
     rsx! {
         div {
             id: "context-menu",
@@ -362,7 +735,7 @@ fn context_menu(ctx_menu: Signal<ContextMenu>, position: Signal<Position>) -> El
                             true => format!("Launch {:?}", entry.name),
                             false => action.name.to_owned(),
                         };
-                        (idx, name, use_context::<Ctx>())
+                        (idx, name, ctx.clone())
                     }) {
                     li {
                         class: "menu-action",
@@ -371,7 +744,7 @@ fn context_menu(ctx_menu: Signal<ContextMenu>, position: Signal<Position>) -> El
                             onclick: move |_| {
                                 position.with_mut(|p| p.subpos = idx);
                                 let mut pos = position.clone();
-                                ctx.write().expect("failed to write ctx").execute(index, &mut pos);
+                                ctx.write().expect("failed to write ctx").execute(BTreeSet::from([index]), &mut pos);
                             },
                             "{name}"
                         }
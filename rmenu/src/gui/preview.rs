@@ -0,0 +1,30 @@
+//! GUI Entry Preview Generation
+use std::process::{Command, Stdio};
+
+use tokio::sync::oneshot;
+
+/// Run Preview Command and Collect its Output
+fn command_output(command: &str) -> String {
+    log::debug!("running preview command: {command:?}");
+    let output = Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+    match output {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).to_string(),
+        Err(err) => {
+            log::error!("preview command failed: {err:?}");
+            String::new()
+        }
+    }
+}
+
+/// Run Preview Command on Background Thread without Blocking the GUI
+pub async fn run_command(command: String) -> String {
+    let (sender, receiver) = oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(command_output(&command));
+    });
+    receiver.await.unwrap_or_default()
+}
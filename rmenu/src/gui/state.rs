@@ -1,11 +1,19 @@
+use std::collections::BTreeSet;
+use std::fmt::Display;
 use std::fs::read_to_string;
+use std::time::{Duration, Instant};
 
 use dioxus::html::geometry::euclid::Point2D;
 use dioxus::prelude::*;
-use rmenu_plugin::Entry;
+use rmenu_plugin::{Action, Entry};
+use tokio::sync::mpsc::UnboundedReceiver;
 
-use crate::config::{Config, Keybind};
-use crate::server::Server;
+use crate::config::{Config, Format, KeyAction, KeyConfig, Keybind, Keystroke, WindowSize};
+use crate::daemon::ShowRequest;
+use crate::exit::Exit;
+use crate::search::{Match, SearchResult};
+use crate::server::{PluginEvent, RMenuError, Scored, Server, Snapshot};
+use crate::watch::Reload;
 
 /// Builder Object for Constructing Context
 #[derive(Debug, Default)]
@@ -13,6 +21,9 @@ pub struct ContextBuilder {
     css: Option<String>,
     theme: Option<String>,
     config: Option<Config>,
+    print: Option<Format>,
+    requests: Option<UnboundedReceiver<ShowRequest>>,
+    reloads: Option<UnboundedReceiver<Reload>>,
 }
 
 impl ContextBuilder {
@@ -28,54 +39,78 @@ impl ContextBuilder {
         self.config = Some(config);
         self
     }
+    pub fn with_daemon(mut self, requests: Option<UnboundedReceiver<ShowRequest>>) -> Self {
+        self.requests = requests;
+        self
+    }
+    pub fn with_reloads(mut self, reloads: Option<UnboundedReceiver<Reload>>) -> Self {
+        self.reloads = reloads;
+        self
+    }
+    pub fn with_print(mut self, print: Option<Format>) -> Self {
+        self.print = print;
+        self
+    }
     pub fn build(self, mut server: Server) -> Context {
         let mut cfg = self.config.unwrap_or_default();
-        let entries = server.search(&mut cfg, "").expect("initial search failed");
-
-        let home = shellexpand::tilde("~/").to_string();
-        let css = self
-            .css
-            .or(cfg.css.clone())
-            .map(|f| shellexpand::tilde(&f).to_string())
-            .map(read_to_string)
-            .map(|f| {
-                let css = f.unwrap_or_else(|err| {
-                    log::error!("Failed to load CSS: {err:?}");
-                    String::new()
-                });
-                css.replace("~/", &home)
-            })
-            .unwrap_or_default();
-        let theme = self
-            .theme
-            .clone()
-            .map(|f| shellexpand::tilde(&f).to_string())
-            .map(read_to_string)
-            .map(|f| {
-                let css = f.unwrap_or_else(|err| {
-                    log::error!("Failed to load Theme: {err:?}");
-                    String::new()
-                });
-                css.replace("~/", &home)
-            })
-            .unwrap_or_default();
+        let (entries, error) = match server.search(&mut cfg, "") {
+            Ok(entries) => (entries, None),
+            Err(err) => (server.results(&cfg), Some(err)),
+        };
+
+        let css_file = self.css.clone().or(cfg.css.clone());
+        let css = read_css(css_file.as_ref(), "CSS");
+        let theme = read_css(self.theme.as_ref(), "Theme");
         Context {
             quit: false,
+            exit: error.as_ref().map(|e| e.exit()).unwrap_or_default(),
+            error,
+            print: self.print,
+            daemon: self.requests.is_some(),
+            requests: self.requests,
+            reloads: self.reloads,
             css,
+            css_file,
+            css_override: self.css,
             theme,
+            theme_file: self.theme,
+            window_size: cfg.window.size.clone(),
             placeholder: cfg.search.placeholder.clone().unwrap_or_default(),
             use_icons: cfg.use_icons,
             use_comments: cfg.use_comments,
+            use_preview: cfg.use_preview,
             config: cfg,
 
+            events: server.take_events(),
             server,
             search: String::new(),
+            generation: 0,
+            scoring: false,
+            stale: false,
             num_results: entries.len(),
             entries,
+            marked: BTreeSet::new(),
+            mode: InputMode::default(),
+            chord: Chord::default(),
         }
     }
 }
 
+/// Read Stylesheet and Expand Home Directory References
+fn read_css(path: Option<&String>, kind: &str) -> String {
+    let home = shellexpand::tilde("~/").to_string();
+    path.map(|f| shellexpand::tilde(f).to_string())
+        .map(read_to_string)
+        .map(|f| {
+            let css = f.unwrap_or_else(|err| {
+                log::error!("Failed to load {kind}: {err:?}");
+                String::new()
+            });
+            css.replace("~/", &home)
+        })
+        .unwrap_or_default()
+}
+
 /// Custom ContextMenu Tracker
 #[derive(Debug, Default)]
 pub struct ContextMenu {
@@ -122,24 +157,114 @@ impl Position {
     }
 }
 
+/// Keyboard Input Mode (Normal Mode Requires Modal Keybinds)
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum InputMode {
+    #[default]
+    Insert,
+    Normal,
+}
+
+impl Display for InputMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!("{self:?}").to_lowercase())
+    }
+}
+
+/// Pending Keystrokes for Multi-Key Keybinds
+#[derive(Debug, Default)]
+struct Chord {
+    keys: Vec<Keystroke>,
+    last: Option<Instant>,
+}
+
+impl Chord {
+    /// Append Keystroke to Sequence (Restarting it if Timed-Out)
+    fn push(&mut self, stroke: Keystroke, timeout: Duration) -> Vec<Keystroke> {
+        if self.last.is_some_and(|t| t.elapsed() > timeout) {
+            self.keys.clear();
+        }
+        self.keys.push(stroke);
+        self.last = Some(Instant::now());
+        self.keys.clone()
+    }
+    fn clear(&mut self) {
+        self.keys.clear();
+        self.last = None;
+    }
+    /// Feed Keystroke and Return the Keys to Dispatch (None while Incomplete)
+    ///
+    /// An unmatched sequence is dropped and the latest keystroke is retried
+    /// on its own, so it may still start a new sequence.
+    fn feed(
+        &mut self,
+        stroke: Keystroke,
+        binds: &[&Keybind],
+        timeout: Duration,
+    ) -> Option<Vec<Keystroke>> {
+        let matches = |keys: &[Keystroke]| binds.iter().any(|b| b.matches(keys));
+        let prefix = |keys: &[Keystroke]| binds.iter().any(|b| b.is_prefix(keys));
+        let mut keys = self.push(stroke.clone(), timeout);
+        if keys.len() > 1 && !matches(&keys) && !prefix(&keys) {
+            self.clear();
+            keys = self.push(stroke, timeout);
+        }
+        // wait for the next keystroke when sequence is incomplete
+        if !matches(&keys) && prefix(&keys) {
+            return None;
+        }
+        self.clear();
+        Some(keys)
+    }
+}
+
 /// Alias for Signal wrapped Position
 type Pos = Signal<Position>;
 type Results = Signal<Vec<usize>>;
+/// Generation of Watched Stylesheets, Bumped on each Reload
+type Styles = Signal<u64>;
+
+/// Plugin Mode Rendered within the Mode Switcher
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModeTab {
+    pub name: String,
+    pub label: String,
+    pub icon: Option<String>,
+    pub active: bool,
+}
 
 /// Contain and Track Search Results
 pub struct Context {
     pub quit: bool,
+    pub exit: Exit,
+    error: Option<RMenuError>,
+    pub daemon: bool,
     pub css: String,
     pub theme: String,
     pub config: Config,
+    css_file: Option<String>,
+    css_override: Option<String>,
+    theme_file: Option<String>,
+    window_size: WindowSize,
     pub placeholder: String,
     pub use_icons: bool,
     pub use_comments: bool,
+    pub use_preview: bool,
+    pub mode: InputMode,
     // search results and controls
+    requests: Option<UnboundedReceiver<ShowRequest>>,
+    reloads: Option<UnboundedReceiver<Reload>>,
+    events: Option<UnboundedReceiver<PluginEvent>>,
     server: Server,
+    print: Option<Format>,
     search: String,
-    entries: Vec<Entry>,
+    generation: u64,
+    scoring: bool,
+    stale: bool,
+    entries: Vec<SearchResult>,
     num_results: usize,
+    marked: BTreeSet<usize>,
+    chord: Chord,
 }
 
 impl Context {
@@ -147,41 +272,287 @@ impl Context {
 
     pub fn next_mode(&mut self, pos: &mut Pos, results: &mut Results) {
         let _ = pos.with_mut(|p| p.reset());
-        self.server.next_plugin();
+        if let Err(err) = self.server.next_plugin() {
+            self.set_error(err);
+            return;
+        }
+        self.load_mode_config();
         results.set(self.set_search(&self.search.clone(), pos));
     }
 
     pub fn prev_mode(&mut self, pos: &mut Pos, results: &mut Results) {
         let _ = pos.with_mut(|p| p.reset());
-        self.server.prev_plugin();
+        if let Err(err) = self.server.prev_plugin() {
+            self.set_error(err);
+            return;
+        }
+        self.load_mode_config();
         results.set(self.set_search(&self.search.clone(), pos));
     }
 
+    /// Switch to the Specified Plugin Mode
+    pub fn switch_mode(&mut self, mode: &str, pos: &mut Pos, results: &mut Results) {
+        let _ = pos.with_mut(|p| p.reset());
+        if let Err(err) = self.server.set_mode(mode) {
+            self.set_error(err);
+            return;
+        }
+        self.load_mode_config();
+        results.set(self.set_search(&self.search.clone(), pos));
+    }
+
+    /// Restore Config Snapshot of the Current Plugin Mode
+    fn load_mode_config(&mut self) {
+        if let Some(config) = self.server.mode_config() {
+            self.config = config.clone();
+        }
+    }
+
+    /// Reload CSS and Resize Window when Changed by the Current Config
+    fn apply_config(&mut self) {
+        let css_file = self.css_override.clone().or(self.config.css.clone());
+        if css_file != self.css_file {
+            log::debug!("loading css {css_file:?}");
+            self.css = read_css(css_file.as_ref(), "CSS");
+            self.css_file = css_file;
+        }
+        if self.config.window.size != self.window_size {
+            let size = self.config.window.logical_size();
+            log::debug!("resizing window to {size:?}");
+            dioxus_desktop::window().set_inner_size(size);
+            self.window_size = self.config.window.size.clone();
+        }
+    }
+
+    /// Plugin Mode Tabs in Order
+    pub fn modes(&self) -> Vec<ModeTab> {
+        self.server
+            .modes()
+            .iter()
+            .map(|name| {
+                let plugin = self.config.plugins.get(name);
+                ModeTab {
+                    name: name.to_owned(),
+                    label: plugin
+                        .and_then(|p| p.label.clone())
+                        .unwrap_or_else(|| name.to_owned()),
+                    icon: plugin.and_then(|p| p.icon.clone()),
+                    active: self.server.is_active(name),
+                }
+            })
+            .collect()
+    }
+
+    // ** Daemon Management **
+
+    #[inline]
+    pub fn take_requests(&mut self) -> Option<UnboundedReceiver<ShowRequest>> {
+        self.requests.take()
+    }
+
+    /// Activate Requested Plugins and Reset Search State
+    pub fn show(&mut self, request: &ShowRequest, pos: &mut Pos, results: &mut Results) {
+        if let Err(err) = self.server.set_active(request.plugins()) {
+            log::error!("invalid show request: {err:?}");
+        }
+        self.load_mode_config();
+        self.quit = false;
+        self.exit = Exit::default();
+        self.error = None;
+        self.mode = InputMode::Insert;
+        self.chord.clear();
+        results.set(self.set_search("", pos));
+    }
+
+    // ** Hot-Reload Management **
+
+    #[inline]
+    pub fn take_reloads(&mut self) -> Option<UnboundedReceiver<Reload>> {
+        self.reloads.take()
+    }
+
+    /// Apply Modified Config or Stylesheets
+    pub fn reload(
+        &mut self,
+        reload: Reload,
+        pos: &mut Pos,
+        results: &mut Results,
+        styles: &mut Styles,
+    ) {
+        match reload {
+            Reload::Config(configs) => {
+                self.server.set_configs(configs);
+                self.load_mode_config();
+                results.set(self.set_search(&self.search.clone(), pos));
+            }
+            Reload::Css => {
+                self.css = read_css(self.css_file.as_ref(), "CSS");
+                self.theme = read_css(self.theme_file.as_ref(), "Theme");
+                *styles.write() += 1;
+            }
+        }
+    }
+
+    // ** Error Management **
+
+    #[inline]
+    pub fn error(&self) -> Option<&RMenuError> {
+        self.error.as_ref()
+    }
+
+    /// Display Error in Banner and Exit with its Status if Cancelled
+    pub fn set_error(&mut self, err: RMenuError) {
+        log::error!("{err:?}");
+        self.exit = err.exit();
+        self.error = Some(err);
+    }
+
+    /// Dismiss Error Banner along with its Exit Status
+    pub fn clear_error(&mut self, pos: &mut Pos) {
+        self.reset_error();
+        pos.with_mut(|_| {});
+    }
+
+    fn reset_error(&mut self) {
+        if self.error.take().is_some() {
+            self.exit = Exit::default();
+        }
+    }
+
     // ** Search Results Management  **
 
     pub fn all_results(&self) -> Vec<usize> {
         (0..self.entries.len()).collect()
     }
 
+    /// Search and Score Results Immediately
     pub fn set_search(&mut self, search: &str, pos: &mut Pos) -> Vec<usize> {
-        let _ = pos.with_mut(|p| p.reset());
+        let snapshot = self.prepare_search(search);
+        self.publish_search(snapshot.score(), pos)
+    }
+
+    /// Send Search to Sources and Capture Snapshot to Score on a Worker
+    pub fn prepare_search(&mut self, search: &str) -> Snapshot {
         self.search = search.to_owned();
-        self.entries = self
-            .server
-            .search(&mut self.config, search)
-            .expect("search failed");
+        self.generation += 1;
+        self.scoring = true;
+        self.stale = false;
+        match self.server.prepare(&mut self.config, search) {
+            Ok(snapshot) => {
+                self.reset_error();
+                snapshot
+            }
+            Err(err) => {
+                self.set_error(err);
+                self.server.snapshot(&self.config)
+            }
+        }
+    }
+
+    /// Display Results Scored from a Search Snapshot
+    pub fn publish_search(&mut self, scored: Scored, pos: &mut Pos) -> Vec<usize> {
+        let _ = pos.with_mut(|p| p.reset());
+        self.scoring = false;
+        let entries = self.server.publish(scored);
+        self.set_entries(entries)
+    }
+
+    /// Capture Snapshot to Rescore when Events Arrived Mid-Search
+    pub fn rescore(&mut self) -> Option<(u64, Snapshot)> {
+        if !std::mem::take(&mut self.stale) {
+            self.scoring = false;
+            return None;
+        }
+        self.scoring = true;
+        Some((self.generation, self.server.snapshot(&self.config)))
+    }
+
+    #[inline]
+    pub fn take_events(&mut self) -> Option<UnboundedReceiver<PluginEvent>> {
+        self.events.take()
+    }
+
+    /// Apply Streamed Plugin Events and Capture Snapshot to Rescore on Change
+    ///
+    /// Events arriving while a search is being scored mark it stale instead,
+    /// so it is rescored once published rather than racing it.
+    pub fn handle_events(&mut self, events: Vec<PluginEvent>) -> Option<(u64, Snapshot)> {
+        let mut changed = false;
+        for event in events {
+            match self.server.handle_event(&mut self.config, event) {
+                Ok(updated) => changed |= updated,
+                Err(err) => {
+                    self.set_error(err);
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            return None;
+        }
+        if self.scoring {
+            self.stale = true;
+            return None;
+        }
+        Some((self.generation, self.server.snapshot(&self.config)))
+    }
+
+    /// Display Results Rescored after Plugin Events unless Searched Since
+    pub fn publish_events(
+        &mut self,
+        generation: u64,
+        scored: Scored,
+        pos: &mut Pos,
+    ) -> Option<Vec<usize>> {
+        if generation != self.generation {
+            return None;
+        }
+        let entries = self.server.publish(scored);
+        let results = self.set_entries(entries);
+        if pos.with(|p| p.pos >= self.num_results) {
+            pos.with_mut(|p| p.reset());
+        }
+        Some(results)
+    }
+
+    fn set_entries(&mut self, entries: Vec<SearchResult>) -> Vec<usize> {
+        // carry marks over to the same entries within the new results
+        let marked: Vec<SearchResult> = self
+            .marked
+            .iter()
+            .filter_map(|i| self.entries.get(*i).cloned())
+            .collect();
+        self.marked = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| {
+                marked
+                    .iter()
+                    .any(|m| m.source == r.source && m.entry == r.entry)
+            })
+            .map(|(i, _)| i)
+            .collect();
+        self.entries = entries;
         self.num_results = self.entries.len();
+        self.apply_config();
         self.placeholder = self.server.placeholder(&self.config);
         self.use_icons = self.config.use_icons
             && self
                 .entries
                 .iter()
-                .any(|e| e.icon.is_some() || e.icon_alt.is_some());
+                .any(|r| r.entry.icon.is_some() || r.entry.icon_alt.is_some());
         self.use_comments =
-            self.config.use_comments && self.entries.iter().any(|e| e.comment.is_some());
+            self.config.use_comments && self.entries.iter().any(|r| r.entry.comment.is_some());
+        self.update_preview();
         (0..self.num_results).collect()
     }
 
+    /// Only Display Preview Panel when Enabled and Entries Support it
+    fn update_preview(&mut self) {
+        self.use_preview =
+            self.config.use_preview && self.entries.iter().any(|r| r.entry.preview.is_some());
+    }
+
     pub fn calc_limit(&self, pos: &Pos) -> usize {
         let pos = pos.with(|p| p.pos);
         let page_size = self.config.page_size;
@@ -199,15 +570,46 @@ impl Context {
     }
 
     #[inline]
-    pub fn get_entry(&self, index: usize) -> &Entry {
-        &self.entries[index]
+    pub fn get_entry(&self, index: usize) -> Option<&Entry> {
+        self.entries.get(index).map(|r| &r.entry)
+    }
+
+    #[inline]
+    pub fn get_matches(&self, index: usize) -> &Match {
+        &self.entries[index].matches
+    }
+
+    #[inline]
+    pub fn get_source(&self, index: usize) -> &str {
+        &self.entries[index].source
     }
 
     // ** Keybind Management **
 
     #[inline]
-    fn matches(&self, bind: &Vec<Keybind>, mods: &Modifiers, key: &Code) -> bool {
-        bind.iter().any(|b| &b.mods == mods && &b.key == key)
+    fn matches(&self, bind: &Vec<Keybind>, keys: &[Keystroke]) -> bool {
+        bind.iter().any(|b| b.matches(keys))
+    }
+
+    /// All Keybinds Active in the Given Input Mode
+    fn active_binds(keybinds: &KeyConfig, mode: InputMode) -> Vec<&Keybind> {
+        let mut binds: Vec<&Keybind> = keybinds.binds().collect();
+        if mode == InputMode::Normal {
+            binds.extend(keybinds.normal.binds());
+        }
+        binds
+    }
+
+    /// Switch Input Mode and Move Focus to Match
+    pub fn set_mode(&mut self, mode: InputMode, pos: &mut Pos) {
+        log::debug!("input-mode {mode}");
+        self.mode = mode;
+        let js = match mode {
+            InputMode::Insert => "document.getElementById('search').focus()",
+            InputMode::Normal => "document.getElementById('content').focus()",
+        };
+        document::eval(js);
+        pos.with_mut(|_| {});
     }
 
     fn scroll(&self, pos: usize) {
@@ -224,24 +626,130 @@ impl Context {
         self.scroll(pos.with(|p| p.pos) + 3);
     }
 
-    //NOTE: using with_mut to trigger rendering update
-    pub fn execute(&mut self, index: usize, pos: &mut Pos) {
-        let Some(entry) = self.entries.get(index).cloned() else {
-            log::error!("execute => invalid index {index:?}");
+    // ** Selection Management **
+
+    #[inline]
+    pub fn is_marked(&self, index: usize) -> bool {
+        self.marked.contains(&index)
+    }
+
+    /// Marked Entries (if any) or Else the Specified Entry
+    pub fn selection(&self, index: usize) -> BTreeSet<usize> {
+        match self.marked.is_empty() {
+            true => BTreeSet::from([index]),
+            false => self.marked.clone(),
+        }
+    }
+
+    pub fn toggle_mark(&mut self, index: usize, pos: &mut Pos) {
+        if !self.config.multi_select {
             return;
-        };
+        }
+        if !self.marked.remove(&index) {
+            self.marked.insert(index);
+        }
+        self.move_down(1, pos);
+    }
+
+    //NOTE: using with_mut to trigger rendering update
+    pub fn execute(&mut self, indices: BTreeSet<usize>, pos: &mut Pos) {
         let (pos, subpos) = pos.with_mut(|p| (p.pos, p.subpos));
         log::debug!("execute-pos {pos} {subpos}");
-        let Some(action) = entry.actions.get(subpos) else {
+        // action menu only applies when executing a single entry
+        let subpos = match indices.len() {
+            1 => subpos,
+            _ => 0,
+        };
+        let mut selected = vec![];
+        for index in indices {
+            let Some(result) = self.entries.get(index).cloned() else {
+                log::error!("execute => invalid index {index:?}");
+                continue;
+            };
+            if let Some(action) = result.entry.actions.get(subpos).cloned() {
+                selected.push((result, Some(subpos), action));
+            }
+        }
+        self.run_actions(selected, Exit::Selected);
+    }
+
+    /// Execute User-Defined Keybind Action on the Selected Entries
+    pub fn execute_custom(&mut self, index: usize, custom: usize, pos: &mut Pos) {
+        let Some((_, target)) = self.config.keybinds.custom.get(custom).cloned() else {
+            log::error!("execute_custom => invalid keybind {custom:?}");
             return;
         };
-        log::debug!("execute-entry {entry:?}");
-        log::debug!("execute-action: {action:?}");
-        self.cleanup(); // ensure everything is cleaned up before exec
-        crate::exec::execute(action, self.config.terminal.clone());
+        log::debug!("execute-custom {target:?}");
+        let selected = self
+            .selection(index)
+            .into_iter()
+            .filter_map(|i| self.entries.get(i).cloned())
+            .filter_map(|result| {
+                let actions = &result.entry.actions;
+                let (idx, action) = match &target {
+                    KeyAction::Action(idx) => (Some(*idx), actions.get(*idx).cloned()),
+                    KeyAction::Named(name) => {
+                        let idx = actions.iter().position(|a| &a.name == name);
+                        (idx, idx.map(|i| actions[i].clone()))
+                    }
+                    KeyAction::Command(cmd) => {
+                        (None, crate::exec::template_action(cmd, &result.entry))
+                    }
+                };
+                action.map(|action| (result, idx, action))
+            })
+            .collect();
+        pos.with_mut(|_| {});
+        self.run_actions(selected, Exit::Custom(custom));
+    }
+
+    /// Record and Execute (or Print) Actions for the Selected Results
+    fn run_actions(&mut self, selected: Vec<(SearchResult, Option<usize>, Action)>, exit: Exit) {
+        if selected.is_empty() {
+            return;
+        }
+        for (result, _, action) in selected.iter() {
+            log::debug!("execute-entry {:?}", result.entry);
+            log::debug!("execute-action: {action:?}");
+            self.server.record(result);
+        }
+        match self.print {
+            Some(format) => {
+                let entries = selected
+                    .iter()
+                    .map(|(result, idx, _)| (&result.entry, *idx))
+                    .collect();
+                crate::exec::print_selection(format, entries);
+            }
+            None => {
+                let term = self.config.terminal.clone();
+                let actions: Vec<Action> = selected.into_iter().map(|(_, _, a)| a).collect();
+                let commands = match crate::exec::build_commands(&actions, term) {
+                    Ok(commands) => commands,
+                    Err(err) => return self.set_error(err),
+                };
+                if !self.daemon {
+                    self.cleanup(); // ensure everything is cleaned up before exec
+                }
+                if let Err(err) = crate::exec::execute(commands, self.daemon) {
+                    // plugins are already stopped so only the daemon may continue
+                    self.set_error(err);
+                    self.quit = !self.daemon;
+                    return;
+                }
+            }
+        }
+        self.exit = exit;
+        self.marked.clear();
         self.quit = true;
     }
 
+    /// Close Menu without Selecting an Entry
+    pub fn cancel(&mut self, pos: &mut Pos) {
+        self.quit = true;
+        pos.with_mut(|_| {});
+    }
+
     pub fn handle_keybinds(
         &mut self,
         event: KeyboardEvent,
@@ -249,35 +757,106 @@ impl Context {
         pos: &mut Pos,
         results: &mut Results,
     ) {
-        let code = event.code();
-        let modifiers = event.modifiers();
+        let stroke = Keystroke {
+            mods: event.modifiers(),
+            key: event.code(),
+        };
+        if stroke.is_modifier() {
+            return;
+        }
+        // nothing is typed into the search while in normal mode
+        if self.mode == InputMode::Normal {
+            event.prevent_default();
+        }
+        let timeout = Duration::from_millis(self.config.keybinds.chord_timeout_ms);
+        let binds = Self::active_binds(&self.config.keybinds, self.mode);
+        let Some(keys) = self.chord.feed(stroke, &binds, timeout) else {
+            event.prevent_default();
+            return;
+        };
+        let custom = self
+            .config
+            .keybinds
+            .custom
+            .iter()
+            .position(|(bind, _)| bind.matches(&keys));
+        if let Some(custom) = custom {
+            return self.execute_custom(index, custom, pos);
+        }
+        if self.mode == InputMode::Normal && self.handle_normal(&keys, index, pos) {
+            return;
+        }
         let keybinds = &self.config.keybinds;
-        if self.matches(&keybinds.exec, &modifiers, &code) {
-            self.execute(index, pos);
-        } else if self.matches(&keybinds.exit, &modifiers, &code) {
-            self.quit = true;
-            pos.with_mut(|_| {});
-        } else if self.matches(&keybinds.move_next, &modifiers, &code) {
+        if self.matches(&keybinds.exec, &keys) {
+            self.execute(self.selection(index), pos);
+        } else if self.config.modal
+            && self.mode == InputMode::Insert
+            && self.matches(&keybinds.normal_mode, &keys)
+        {
+            self.set_mode(InputMode::Normal, pos);
+        } else if self.matches(&keybinds.exit, &keys) {
+            self.cancel(pos);
+        } else if self.matches(&keybinds.move_next, &keys) {
             self.move_next(index, pos);
             self.scroll_down(pos);
-        } else if self.matches(&keybinds.move_prev, &modifiers, &code) {
+        } else if self.matches(&keybinds.move_prev, &keys) {
             self.move_prev(pos);
             self.scroll_up(pos);
-        } else if self.matches(&keybinds.open_menu, &modifiers, &code) {
+        } else if self.matches(&keybinds.open_menu, &keys) {
             self.open_menu(index, pos);
-        } else if self.matches(&keybinds.close_menu, &modifiers, &code) {
+        } else if self.matches(&keybinds.close_menu, &keys) {
             self.close_menu(pos);
-        } else if self.matches(&keybinds.jump_next, &modifiers, &code) {
+        } else if self.matches(&keybinds.jump_next, &keys) {
             self.move_down(self.config.jump_dist, pos);
             self.scroll_down(pos);
-        } else if self.matches(&keybinds.jump_prev, &modifiers, &code) {
+        } else if self.matches(&keybinds.jump_prev, &keys) {
             self.move_up(self.config.jump_dist, pos);
             self.scroll_up(pos);
-        } else if self.matches(&keybinds.mode_next, &modifiers, &code) {
+        } else if self.matches(&keybinds.mode_next, &keys) {
             self.next_mode(pos, results);
-        } else if self.matches(&keybinds.mode_prev, &modifiers, &code) {
+        } else if self.matches(&keybinds.mode_prev, &keys) {
             self.prev_mode(pos, results);
+        } else if self.matches(&keybinds.toggle_preview, &keys) {
+            self.toggle_preview(pos);
+        } else if self.matches(&keybinds.toggle_mark, &keys) {
+            self.toggle_mark(index, pos);
+            self.scroll_down(pos);
+        }
+    }
+
+    /// Handle Normal-Mode Specific Keybinds (Returns True if Matched)
+    fn handle_normal(&mut self, keys: &[Keystroke], index: usize, pos: &mut Pos) -> bool {
+        let keybinds = &self.config.keybinds.normal;
+        if self.matches(&keybinds.exit, keys) {
+            self.cancel(pos);
+        } else if self.matches(&keybinds.insert, keys) {
+            self.set_mode(InputMode::Insert, pos);
+        } else if self.matches(&keybinds.move_next, keys) {
+            self.move_next(index, pos);
+            self.scroll_down(pos);
+        } else if self.matches(&keybinds.move_prev, keys) {
+            self.move_prev(pos);
+            self.scroll_up(pos);
+        } else if self.matches(&keybinds.open_menu, keys) {
+            self.open_menu(index, pos);
+        } else if self.matches(&keybinds.close_menu, keys) {
+            self.close_menu(pos);
+        } else if self.matches(&keybinds.jump_next, keys) {
+            self.move_down(self.config.jump_dist, pos);
+            self.scroll_down(pos);
+        } else if self.matches(&keybinds.jump_prev, keys) {
+            self.move_up(self.config.jump_dist, pos);
+            self.scroll_up(pos);
+        } else if self.matches(&keybinds.first, keys) {
+            self.move_up(self.num_results, pos);
+            self.scroll_up(pos);
+        } else if self.matches(&keybinds.last, keys) {
+            self.move_down(self.num_results, pos);
+            self.scroll_down(pos);
+        } else {
+            return false;
         }
+        true
     }
 
     // ** Position Management **
@@ -306,7 +885,7 @@ impl Context {
     }
 
     pub fn move_next(&self, index: usize, pos: &mut Pos) {
-        let Some(entry) = self.entries.get(index) else {
+        let Some(entry) = self.entries.get(index).map(|r| &r.entry) else {
             log::error!("move_next => invalid index {index:?}");
             return;
         };
@@ -318,7 +897,7 @@ impl Context {
     }
 
     pub fn open_menu(&self, index: usize, pos: &mut Pos) {
-        let Some(entry) = self.entries.get(index) else {
+        let Some(entry) = self.entries.get(index).map(|r| &r.entry) else {
             log::error!("open_menu => invalid index {index:?}");
             return;
         };
@@ -332,9 +911,61 @@ impl Context {
         pos.with_mut(|s| s.subpos = 0);
     }
 
+    // ** Preview Management **
+
+    pub fn toggle_preview(&mut self, pos: &mut Pos) {
+        self.config.use_preview = !self.config.use_preview;
+        self.update_preview();
+        pos.with_mut(|_| {});
+    }
+
     //** Cleanup  **
 
     pub fn cleanup(&mut self) {
         self.server.cleanup();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn stroke(key: &str) -> Keystroke {
+        Keystroke::from_str(key).unwrap()
+    }
+
+    #[test]
+    fn chord_restarts_after_unmatched_key() {
+        let gg = Keybind::from_str("g g").unwrap();
+        let binds = vec![&gg];
+        let timeout = Duration::from_secs(60);
+        let mut chord = Chord::default();
+        assert_eq!(chord.feed(stroke("g"), &binds, timeout), None);
+        assert_eq!(
+            chord.feed(stroke("x"), &binds, timeout),
+            Some(vec![stroke("x")])
+        );
+        assert_eq!(chord.feed(stroke("g"), &binds, timeout), None);
+        assert_eq!(
+            chord.feed(stroke("g"), &binds, timeout),
+            Some(gg.keys.clone())
+        );
+    }
+
+    #[test]
+    fn chord_unmatched_key_starts_new_sequence() {
+        let gg = Keybind::from_str("g g").unwrap();
+        let dd = Keybind::from_str("d d").unwrap();
+        let binds = vec![&gg, &dd];
+        let timeout = Duration::from_secs(60);
+        let mut chord = Chord::default();
+        assert_eq!(chord.feed(stroke("g"), &binds, timeout), None);
+        assert_eq!(chord.feed(stroke("d"), &binds, timeout), None);
+        assert_eq!(
+            chord.feed(stroke("d"), &binds, timeout),
+            Some(dd.keys.clone())
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! RMenu Plugin Selection History (Frecency) Store
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use rmenu_plugin::Entry;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::XDG_PREFIX;
+
+static DAY_SECS: u64 = 24 * 60 * 60;
+
+/// Relative weight of each age bucket (in days) when calculating frecency
+static RECENCY_BUCKETS: [(u64, f64); 4] = [(4, 1.0), (14, 0.7), (31, 0.5), (90, 0.3)];
+static RECENCY_MIN: f64 = 0.1;
+
+/// Scale of frecency score relative to search match scores
+static FRECENCY_SCALE: f64 = 16.0;
+
+#[derive(Debug, Error)]
+pub enum HistoryError {
+    #[error("History File Error")]
+    FileError(#[from] std::io::Error),
+    #[error("Encoding Error")]
+    EncodingError(#[from] serde_json::Error),
+}
+
+#[inline]
+fn history_file(name: &str) -> Result<PathBuf, std::io::Error> {
+    xdg::BaseDirectories::with_prefix(XDG_PREFIX)?.place_data_file(format!("{name}.history"))
+}
+
+#[inline]
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or(Duration::from_secs(0))
+        .as_secs()
+}
+
+/// Selection Record for a Single Entry
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub count: u64,
+    pub last_used: u64,
+}
+
+impl Record {
+    /// Calculate Frecency Score based on Usage Count and Age
+    pub fn frecency(&self, now: u64) -> i64 {
+        let days = now.saturating_sub(self.last_used) / DAY_SECS;
+        let recency = RECENCY_BUCKETS
+            .iter()
+            .find(|(max, _)| days < *max)
+            .map(|(_, weight)| *weight)
+            .unwrap_or(RECENCY_MIN);
+        (FRECENCY_SCALE * recency * (self.count as f64).ln_1p()) as i64
+    }
+}
+
+/// Persistent Entry Selection History for a Plugin
+#[derive(Debug, Clone)]
+pub struct History {
+    name: String,
+    records: BTreeMap<String, Record>,
+}
+
+impl History {
+    /// Load Plugin History from XDG Data Directory (if Available)
+    ///
+    /// Fails when the data directory cannot be read or created.
+    pub fn load(name: &str) -> Result<Self, HistoryError> {
+        let path = history_file(name)?;
+        let records = match path.exists() {
+            true => serde_json::from_slice(&fs::read(path)?)?,
+            false => BTreeMap::new(),
+        };
+        Ok(Self {
+            name: name.to_owned(),
+            records,
+        })
+    }
+
+    /// Retrieve Frecency Score for the Specified Entry
+    pub fn score(&self, entry: &Entry) -> i64 {
+        self.records
+            .get(&entry.name)
+            .map(|r| r.frecency(now()))
+            .unwrap_or(0)
+    }
+
+    /// Record Entry Selection and Write History to Disk
+    pub fn record(&mut self, entry: &Entry) -> Result<(), HistoryError> {
+        let record = self.records.entry(entry.name.to_owned()).or_default();
+        record.count += 1;
+        record.last_used = now();
+        log::debug!("{:?} recording selection {:?}", self.name, entry.name);
+        let f = fs::File::create(history_file(&self.name)?)?;
+        serde_json::to_writer(f, &self.records)?;
+        Ok(())
+    }
+}
+
+/// Delete Recorded History for the Specified Plugin
+pub fn clear_history(name: &str) -> Result<(), std::io::Error> {
+    let path = history_file(name)?;
+    if path.exists() {
+        log::info!("clearing history for {name:?}");
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
@@ -1,10 +1,16 @@
+mod builtin;
 mod cache;
 mod cli;
 mod config;
+mod daemon;
 mod exec;
+mod exit;
 mod gui;
+mod history;
+mod manifest;
 mod search;
 mod server;
+mod watch;
 
 use clap::Parser;
 use server::ServerBuilder;
@@ -18,33 +24,72 @@ static ENV_ACTIVE_PLUGINS: &'static str = "RMENU_ACTIVE_PLUGINS";
 
 //TODO: remove min-length from search options in rmenu-lib
 
-fn main() -> server::Result<()> {
+fn main() {
     env_logger::init();
+    if let Err(err) = run() {
+        let exit = err.exit();
+        eprintln!("Error: {err:?}");
+        std::process::exit(exit.code());
+    }
+}
 
+fn run() -> server::Result<()> {
     // export self to environment for other scripts
     let exe = rmenu_plugin::self_exe();
     std::env::set_var(ENV_BIN, exe);
 
     // parse cli and retrieve values for app
-    let mut cli = cli::Args::parse();
-    let mut config = cli.get_config()?;
+    let cli = cli::Args::parse();
+
+    // send show request to running daemon
+    cli.check_daemon()?;
+    if let Some(cli::Command::Show) = cli.command {
+        return daemon::send_show(&cli.show_request());
+    }
+
+    let config = cli.get_config()?;
+
+    // list discovered plugins when requested
+    if cli.list_plugins {
+        return cli.list_plugins(&config);
+    }
+
+    // clear plugin history when requested
+    if cli.clear_history {
+        return cli.reset_history(&config);
+    }
 
     // spawn plugin server
-    let mut builder = ServerBuilder::default();
+    let mut builder = ServerBuilder::default().debug_plugins(cli.debug_plugins);
     if let Some(input) = cli.input.as_ref() {
         builder = builder.add_input(cli.format, input)?;
     }
-    if cli.input.is_none() && cli.run.is_empty() {
+    if cli.input.is_none() && cli.run.is_empty() && !cli.daemon {
         builder = builder.add_input(cli.format, "-")?;
     }
-    builder = builder.add_plugins(cli.run.clone(), &mut config)?;
-    let server = builder.build(cli.show.clone())?;
+    builder = builder.add_plugins(cli.plugins(&config), &config)?;
+    let mut server = builder.build(cli.show.clone())?;
+
+    // listen for show requests when running as daemon
+    let requests = match cli.daemon {
+        true => Some(daemon::listen()?),
+        false => None,
+    };
 
-    // update config based on cli-settings and entries
-    config = cli.update_config(config);
+    // snapshot config for each mode w/ plugin options, cli-settings and env
+    let env_plugins = cli::env_plugins();
+    let configs = cli.mode_configs(&config, server.modes(), &env_plugins)?;
 
-    // load additional configuration settings from env
-    cli.load_env(&mut config)?;
+    // watch config and css for changes when requested
+    let reloads = match cli.watch {
+        true => {
+            let modes = server.modes().to_vec();
+            Some(watch::listen(cli.clone(), &configs, modes, env_plugins)?)
+        }
+        false => None,
+    };
+    server.set_configs(configs);
+    let config = server.mode_config().cloned().expect("missing mode config");
 
     // configure css theme and css overrides
     let theme = cli.get_theme();
@@ -58,6 +103,9 @@ fn main() -> server::Result<()> {
         .with_css(cli.css)
         .with_theme(theme)
         .with_config(config)
+        .with_daemon(requests)
+        .with_reloads(reloads)
+        .with_print(cli.print_selection)
         .build(server);
     gui::run(context);
 
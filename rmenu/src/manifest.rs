@@ -0,0 +1,67 @@
+//! Plugin Discovery from Manifest Files
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use crate::config::PluginConfig;
+use crate::server::RMenuError;
+use crate::XDG_PREFIX;
+
+/// Directory (Relative to XDG Config/Data Dirs) Containing Plugin Manifests
+static MANIFEST_DIR: &'static str = "plugins";
+
+#[inline]
+fn is_manifest(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|ext| ext == "yaml" || ext == "yml")
+}
+
+/// Load Plugin Configuration from a Manifest File
+///
+/// Executables starting with `./` are resolved relative to the manifest.
+fn load(path: &Path) -> Result<PluginConfig, String> {
+    let content = read_to_string(path).map_err(|e| e.to_string())?;
+    let mut config: PluginConfig = serde_yaml::from_str(&content).map_err(|e| e.to_string())?;
+    if let Some(exec) = config.exec.first_mut() {
+        if let (Some(rel), Some(dir)) = (exec.strip_prefix("./"), path.parent()) {
+            *exec = dir.join(rel).to_string_lossy().to_string();
+        }
+    }
+    config.manifest = Some(path.to_owned());
+    Ok(config)
+}
+
+/// Discover Plugin Manifests within XDG Config and Data Directories
+///
+/// Plugins are named after their manifest file. When the same name is
+/// found more than once, the user config directory takes precedence
+/// over data directories, and user directories over system ones.
+pub fn discover() -> Result<BTreeMap<String, PluginConfig>, RMenuError> {
+    let dirs = xdg::BaseDirectories::with_prefix(XDG_PREFIX)?;
+    let files: Vec<PathBuf> = dirs
+        .list_config_files(MANIFEST_DIR)
+        .into_iter()
+        .chain(dirs.list_data_files(MANIFEST_DIR))
+        .filter(|path| is_manifest(path))
+        .collect();
+    let mut plugins = BTreeMap::new();
+    for path in files {
+        let Some(name) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+            continue;
+        };
+        if plugins.contains_key(&name) {
+            log::debug!("plugin {name:?} already discovered. skipping {path:?}");
+            continue;
+        }
+        match load(&path) {
+            Ok(config) => {
+                log::debug!("discovered plugin {name:?} in {path:?}");
+                plugins.insert(name, config);
+            }
+            Err(err) => log::error!("invalid plugin manifest {path:?}: {err}"),
+        }
+    }
+    Ok(plugins)
+}
@@ -1,50 +1,363 @@
 //! RMENU Entry Search Function Implementaton
+use std::ops::Range;
+
 use regex::RegexBuilder;
 use rmenu_plugin::{Entry, Search};
 
+use crate::config::SearchMode;
+
+// fuzzy scoring constants (modeled after fzf)
+const SCORE_MATCH: i64 = 16;
+const SCORE_GAP_START: i64 = -3;
+const SCORE_GAP_EXTENSION: i64 = -1;
+const BONUS_BOUNDARY: i64 = SCORE_MATCH / 2;
+const BONUS_NON_WORD: i64 = SCORE_MATCH / 2;
+const BONUS_CAMEL: i64 = BONUS_BOUNDARY + SCORE_GAP_EXTENSION;
+const BONUS_CONSECUTIVE: i64 = -(SCORE_GAP_START + SCORE_GAP_EXTENSION);
+const BONUS_FIRST_CHAR_MULTIPLIER: i64 = 2;
+
+/// Search Match Score and Matched Character Ranges
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Match {
+    pub score: i64,
+    pub name: Vec<Range<usize>>,
+    pub comment: Vec<Range<usize>>,
+}
+
+/// Collapse Sorted Character Positions into Contiguous Ranges
+fn to_ranges(positions: &[usize]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = vec![];
+    for pos in positions {
+        match ranges.last_mut() {
+            Some(last) if last.end == *pos => last.end += 1,
+            _ => ranges.push(*pos..*pos + 1),
+        }
+    }
+    ranges
+}
+
+/// Convert Byte Range within Text to Character Range
+#[inline]
+fn to_char_range(text: &str, range: Range<usize>) -> Range<usize> {
+    let start = text[..range.start].chars().count();
+    start..start + text[range].chars().count()
+}
+
+/// Find all Non-Overlapping Occurrences of Pattern within Text
+fn find_chars(text: &str, pattern: &[char], ignore_case: bool) -> Option<Vec<Range<usize>>> {
+    if pattern.is_empty() {
+        return Some(vec![]);
+    }
+    let chars: Vec<char> = text.chars().map(|c| fold_case(c, ignore_case)).collect();
+    let mut ranges = vec![];
+    let mut idx = 0;
+    while idx + pattern.len() <= chars.len() {
+        if chars[idx..idx + pattern.len()] == *pattern {
+            ranges.push(idx..idx + pattern.len());
+            idx += pattern.len();
+            continue;
+        }
+        idx += 1;
+    }
+    (!ranges.is_empty()).then_some(ranges)
+}
+
+/// Build Match from Name/Comment Ranges Discovered by Finder
+fn find_match<F>(entry: &Entry, finder: F) -> Option<Match>
+where
+    F: Fn(&str) -> Option<Vec<Range<usize>>>,
+{
+    let name = finder(&entry.name);
+    let comment = entry.comment.as_ref().and_then(|c| finder(c));
+    if name.is_none() && comment.is_none() {
+        return None;
+    }
+    Some(Match {
+        score: 0,
+        name: name.unwrap_or_default(),
+        comment: comment.unwrap_or_default(),
+    })
+}
+
+/// Entry Search Result w/ Match Details
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub source: String,
+    pub entry: Entry,
+    pub matches: Match,
+}
+
+impl SearchResult {
+    pub fn new(entry: Entry, matches: Match) -> Self {
+        Self {
+            source: String::new(),
+            entry,
+            matches,
+        }
+    }
+}
+
+/// Dynamic Search Function Returning Match (if Any)
+pub type SearchFn = Box<dyn Fn(&Entry) -> Option<Match>>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CharClass {
+    Lower,
+    Upper,
+    Number,
+    Delimiter,
+    NonWord,
+}
+
+impl CharClass {
+    fn of(c: char) -> Self {
+        match c {
+            c if c.is_lowercase() => Self::Lower,
+            c if c.is_uppercase() => Self::Upper,
+            c if c.is_numeric() => Self::Number,
+            c if c.is_alphabetic() => Self::Lower,
+            c if c.is_whitespace() => Self::Delimiter,
+            '/' | ',' | ':' | ';' | '|' | '-' | '_' | '.' => Self::Delimiter,
+            _ => Self::NonWord,
+        }
+    }
+    #[inline]
+    fn is_word(&self) -> bool {
+        !matches!(self, Self::Delimiter | Self::NonWord)
+    }
+}
+
+/// Calculate Bonus for Matching a Character after the Previous
+fn char_bonus(prev: CharClass, curr: CharClass) -> i64 {
+    if curr.is_word() {
+        return match prev {
+            CharClass::Delimiter | CharClass::NonWord => BONUS_BOUNDARY,
+            CharClass::Lower if curr == CharClass::Upper => BONUS_CAMEL,
+            CharClass::Lower | CharClass::Upper if curr == CharClass::Number => BONUS_CAMEL,
+            _ => 0,
+        };
+    }
+    BONUS_NON_WORD
+}
+
+#[inline]
+fn fold_case(c: char, ignore_case: bool) -> char {
+    match ignore_case {
+        true => c.to_lowercase().next().unwrap_or(c),
+        false => c,
+    }
+}
+
+/// Score Text against Pattern using Smith-Waterman Style Alignment
+///
+/// Returns the best score and the character positions of the matched
+/// pattern within the text when the pattern is a subsequence of the text.
+pub fn fuzzy_match(text: &str, pattern: &[char], ignore_case: bool) -> Option<(i64, Vec<usize>)> {
+    if pattern.is_empty() {
+        return Some((0, vec![]));
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let folded: Vec<char> = chars.iter().map(|c| fold_case(*c, ignore_case)).collect();
+    let (n, m) = (folded.len(), pattern.len());
+    // quick subsequence check before running full alignment
+    let mut pidx = 0;
+    for c in folded.iter() {
+        if pidx < m && *c == pattern[pidx] {
+            pidx += 1;
+        }
+    }
+    if pidx < m {
+        return None;
+    }
+    // precalculate position bonuses
+    let mut prev = CharClass::Delimiter;
+    let bonus: Vec<i64> = chars
+        .iter()
+        .map(|c| {
+            let class = CharClass::of(*c);
+            let bonus = char_bonus(prev, class);
+            prev = class;
+            bonus
+        })
+        .collect();
+    // fill scoring matrix (score, consecutive-count, previous-match)
+    const NONE: i64 = i64::MIN;
+    let mut score = vec![NONE; n * m];
+    let mut consec = vec![0usize; n * m];
+    let mut from = vec![usize::MAX; n * m];
+    for j in 0..m {
+        // best gapped alignment of pattern[..j] ending before i-1
+        let mut gap = NONE;
+        let mut gap_from = usize::MAX;
+        for i in j..n {
+            if j > 0 && i >= 2 {
+                let last = score[(i - 2) * m + j - 1];
+                let extend = match gap == NONE {
+                    true => NONE,
+                    false => gap + SCORE_GAP_EXTENSION,
+                };
+                let start = match last == NONE {
+                    true => NONE,
+                    false => last + SCORE_GAP_START,
+                };
+                if start >= extend && start != NONE {
+                    gap = start;
+                    gap_from = i - 2;
+                } else {
+                    gap = extend;
+                }
+            }
+            if folded[i] != pattern[j] {
+                continue;
+            }
+            let idx = i * m + j;
+            if j == 0 {
+                score[idx] = SCORE_MATCH + bonus[i] * BONUS_FIRST_CHAR_MULTIPLIER;
+                consec[idx] = 1;
+                continue;
+            }
+            // consecutive match directly following previous character
+            let diag = match i > 0 {
+                true => score[(i - 1) * m + j - 1],
+                false => NONE,
+            };
+            if diag != NONE {
+                let count = consec[(i - 1) * m + j - 1] + 1;
+                let b = std::cmp::max(bonus[i], BONUS_CONSECUTIVE);
+                score[idx] = diag + SCORE_MATCH + b;
+                consec[idx] = count;
+                from[idx] = i - 1;
+            }
+            // gapped match following an earlier character
+            if gap != NONE {
+                let gapped = gap + SCORE_MATCH + bonus[i];
+                if gapped > score[idx] {
+                    score[idx] = gapped;
+                    consec[idx] = 1;
+                    from[idx] = gap_from;
+                }
+            }
+        }
+    }
+    // find best final position and backtrack matched positions
+    let (mut best, mut end) = (NONE, usize::MAX);
+    for i in (m - 1)..n {
+        let s = score[i * m + m - 1];
+        if s > best {
+            best = s;
+            end = i;
+        }
+    }
+    if best == NONE {
+        return None;
+    }
+    let mut positions = vec![0; m];
+    let mut i = end;
+    for j in (0..m).rev() {
+        positions[j] = i;
+        i = from[i * m + j];
+    }
+    Some((best, positions))
+}
+
+/// Generate a new Fuzzy Search Function for the Specified Search
+fn new_fuzzyfn(search: &Search) -> SearchFn {
+    let ignore_case = search.ignore_case;
+    let pattern: Vec<char> = search
+        .search
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| fold_case(c, ignore_case))
+        .collect();
+    Box::new(move |entry: &Entry| {
+        let name = fuzzy_match(&entry.name, &pattern, ignore_case);
+        let comment = entry
+            .comment
+            .as_ref()
+            .and_then(|c| fuzzy_match(c, &pattern, ignore_case));
+        // comment matches are weighted lower than name matches
+        let score = match (name.as_ref(), comment.as_ref()) {
+            (None, None) => return None,
+            (Some((n, _)), None) => *n,
+            (None, Some((c, _))) => *c / 2,
+            (Some((n, _)), Some((c, _))) => std::cmp::max(*n, *c / 2),
+        };
+        Some(Match {
+            score,
+            name: name.map(|(_, p)| to_ranges(&p)).unwrap_or_default(),
+            comment: comment.map(|(_, p)| to_ranges(&p)).unwrap_or_default(),
+        })
+    })
+}
+
 /// Generate a new dynamic Search Function based on
 /// Configurtaion Settings and Search-String
-pub fn new_searchfn(search: &Search) -> Box<dyn Fn(&Entry) -> bool> {
+pub fn new_searchfn(search: &Search, mode: &SearchMode) -> SearchFn {
+    // build fuzzy search expression
+    if *mode == SearchMode::Fuzzy {
+        return new_fuzzyfn(search);
+    }
     // build regex search expression
     if search.is_regex {
         let rgx = RegexBuilder::new(&search.search)
             .case_insensitive(search.ignore_case)
             .build();
         let Ok(regex) = rgx else {
-            return Box::new(|_| false);
+            return Box::new(|_| None);
         };
         return Box::new(move |entry: &Entry| {
-            if regex.is_match(&entry.name) {
-                return true;
-            }
-            if let Some(comment) = entry.comment.as_ref() {
-                return regex.is_match(&comment);
-            }
-            false
+            find_match(entry, |text| {
+                regex.is_match(text).then(|| {
+                    regex
+                        .find_iter(text)
+                        .filter(|m| !m.is_empty())
+                        .map(|m| to_char_range(text, m.range()))
+                        .collect()
+                })
+            })
         });
     }
-    // build case-insensitive search expression
-    if search.ignore_case {
-        let matchstr = search.search.to_lowercase();
-        return Box::new(move |entry: &Entry| {
-            if entry.name.to_lowercase().contains(&matchstr) {
-                return true;
-            }
-            if let Some(comment) = entry.comment.as_ref() {
-                return comment.to_lowercase().contains(&matchstr);
-            }
-            false
-        });
+    // build standard string comparison function
+    let ignore_case = search.ignore_case;
+    let pattern: Vec<char> = search
+        .search
+        .chars()
+        .map(|c| fold_case(c, ignore_case))
+        .collect();
+    Box::new(move |entry: &Entry| find_match(entry, |text| find_chars(text, &pattern, ignore_case)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    #[test]
+    fn fuzzy_requires_subsequence() {
+        assert!(fuzzy_match("firefox", &pattern("fx"), false).is_some());
+        assert!(fuzzy_match("firefox", &pattern("xf"), false).is_none());
+        assert_eq!(fuzzy_match("firefox", &[], false), Some((0, vec![])));
+    }
+
+    #[test]
+    fn fuzzy_ignore_case() {
+        assert!(fuzzy_match("FireFox", &pattern("ff"), false).is_none());
+        assert!(fuzzy_match("FireFox", &pattern("ff"), true).is_some());
+    }
+
+    #[test]
+    fn fuzzy_match_positions() {
+        let (_, positions) = fuzzy_match("file manager", &pattern("fm"), false).unwrap();
+        assert_eq!(positions, vec![0, 5]);
+    }
+
+    #[test]
+    fn fuzzy_prefers_word_starts_and_consecutive() {
+        let score = |text: &str, pat: &str| fuzzy_match(text, &pattern(pat), true).unwrap().0;
+        assert!(score("gimp", "gi") > score("logic", "gi"));
+        assert!(score("terminal", "term") > score("thermal tea", "term"));
     }
-    // build standard normal string comparison function
-    let matchstr = search.search.to_owned();
-    Box::new(move |entry: &Entry| {
-        if entry.name.contains(&matchstr) {
-            return true;
-        }
-        if let Some(comment) = entry.comment.as_ref() {
-            return comment.contains(&matchstr);
-        }
-        false
-    })
 }
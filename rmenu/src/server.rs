@@ -1,15 +1,23 @@
 /// RMenu Plugin Result Entry Server
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::fs::File;
 use std::io::{BufRead, BufReader, Read, Write};
 use std::path::PathBuf;
 use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
-use rmenu_plugin::{Entry, Message, Search};
+use rmenu_plugin::{Entry, Message, Search, PROTOCOL_ENV, PROTOCOL_VERSION};
 use thiserror::Error;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
 
-use super::config::{Config, Format, PluginConfig};
-use super::search::new_searchfn;
+use super::config::{Config, Format, PluginConfig, RestartPolicy};
+use super::exit::Exit;
+use super::history::History;
+use super::search::{new_searchfn, SearchResult};
+use super::XDG_PREFIX;
 
 #[derive(Error, Debug)]
 pub enum RMenuError {
@@ -27,6 +35,53 @@ pub enum RMenuError {
     CommandError(Option<ExitStatus>),
     #[error("Invalid JSON Entry Object")]
     InvalidJson(#[from] serde_json::Error),
+    #[error("Daemon Error")]
+    DaemonError(String),
+    #[error("Plugin {plugin:?} Failed: {reason}")]
+    PluginFailed {
+        plugin: String,
+        reason: String,
+        /// last lines written to stderr by the plugin
+        stderr: Vec<String>,
+    },
+    #[error("Plugin {plugin:?} Timed Out after {timeout:?}")]
+    PluginTimeout { plugin: String, timeout: Duration },
+    #[error("No Terminal Executable Found")]
+    NoTerminal,
+    #[error("Invalid Command: {0}")]
+    InvalidCommand(String),
+    #[error("Invalid Arguments: {0}")]
+    InvalidArgs(String),
+    #[error("File Watcher Error")]
+    WatchError(#[from] notify::Error),
+    #[error("XDG Directory Error")]
+    XdgError(#[from] xdg::BaseDirectoriesError),
+    #[error("No Plugins Available")]
+    NoPlugins,
+}
+
+impl RMenuError {
+    /// Process Exit Reason for the Error
+    pub fn exit(&self) -> Exit {
+        match self {
+            Self::InvalidConfig(_)
+            | Self::InvalidKeybind(_)
+            | Self::NoSuchPlugin(_)
+            | Self::InvalidPlugin(_)
+            | Self::InvalidArgs(_)
+            | Self::NoPlugins => Exit::ConfigInvalid,
+            Self::PluginFailed { .. } | Self::PluginTimeout { .. } => Exit::PluginFailed,
+            Self::NoTerminal => Exit::NoTerminal,
+            _ => Exit::Error,
+        }
+    }
+    /// Stderr Output Captured Alongside the Error (if Any)
+    pub fn stderr(&self) -> &[String] {
+        match self {
+            Self::PluginFailed { stderr, .. } => stderr,
+            _ => &[],
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, RMenuError>;
@@ -58,6 +113,7 @@ fn new_search(query: &str, config: &Config) -> Search {
         search: query.to_owned(),
         is_regex: config.search.use_regex,
         ignore_case: config.search.ignore_case,
+        id: None,
     }
 }
 
@@ -73,8 +129,10 @@ fn read_entries<T: Read>(
             Format::Json => {
                 let msg: Message = serde_json::from_str(&line)?;
                 match msg {
-                    Message::Stop => break,
+                    Message::Stop { .. } => break,
+                    Message::Hello(_) => {}
                     Message::Entry(entry) => entries.push(entry),
+                    Message::Batch { entries: batch, .. } => entries.extend(batch),
                     Message::Options(options) => config
                         .update(&options)
                         .map_err(|s| RMenuError::InvalidKeybind(s))?,
@@ -85,13 +143,221 @@ fn read_entries<T: Read>(
     Ok(entries)
 }
 
+/// Plugin Output Observed by Background Threads
+#[derive(Debug)]
+pub enum Output {
+    /// message read from plugin output
+    Message(Message),
+    /// plugin output was closed
+    Closed,
+    /// plugin did not finish replying before its timeout
+    TimedOut,
+    /// plugin without handshake kept its output open after its first reply
+    Running,
+}
+
+/// Event Read from Plugin Output by Background Threads
+#[derive(Debug)]
+pub struct PluginEvent {
+    pub plugin: String,
+    /// plugin process instance (incremented on every restart)
+    pub instance: usize,
+    /// id of the search request this message replies to
+    pub reply: u64,
+    pub output: Output,
+}
+
+/// Read Plugin Output on Background Thread and Forward Messages
+fn spawn_reader<T: Read + Send + 'static>(
+    name: String,
+    instance: usize,
+    format: Format,
+    reader: T,
+    events: UnboundedSender<PluginEvent>,
+) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        // plugins that do not echo request ids must reply sequentially, so
+        // their messages belong to the search after the last stop received
+        let mut reply = 1;
+        for line in BufReader::new(reader).lines().filter_map(|l| l.ok()) {
+            let message = match format {
+                Format::DMenu => Message::Entry(Entry::echo(line.trim(), None)),
+                Format::Json => match serde_json::from_str(&line) {
+                    Ok(message) => message,
+                    Err(err) => {
+                        log::error!("{name:?} invalid message: {err:?}");
+                        continue;
+                    }
+                },
+            };
+            let id = message.id().unwrap_or(reply);
+            let stop = matches!(message, Message::Stop { .. });
+            let event = PluginEvent {
+                plugin: name.to_owned(),
+                instance,
+                reply: id,
+                output: Output::Message(message),
+            };
+            if events.send(event).is_err() {
+                return;
+            }
+            if stop {
+                reply = id + 1;
+            }
+        }
+        log::debug!("{name:?} output closed");
+        let event = PluginEvent {
+            plugin: name,
+            instance,
+            reply,
+            output: Output::Closed,
+        };
+        let _ = events.send(event);
+    })
+}
+
+/// Spawn Single Timeout Timer for a Plugin Instance
+///
+/// Every search sent over the returned channel resets the timer, and a
+/// timeout is only reported for the latest search. Completed replies are
+/// discarded by the plugin when handling the event. The timer exits once
+/// the channel is dropped.
+fn spawn_timer(
+    name: String,
+    instance: usize,
+    timeout: Duration,
+    events: UnboundedSender<PluginEvent>,
+) -> Sender<u64> {
+    let (sender, receiver) = channel();
+    std::thread::spawn(move || {
+        let mut pending: Option<(u64, Instant)> = None;
+        loop {
+            let received = match pending {
+                Some((_, deadline)) => {
+                    receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                }
+                None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Ok(reply) => pending = Some((reply, Instant::now() + timeout)),
+                Err(RecvTimeoutError::Timeout) => {
+                    let Some((reply, _)) = pending.take() else {
+                        continue;
+                    };
+                    let event = PluginEvent {
+                        plugin: name.to_owned(),
+                        instance,
+                        reply,
+                        output: Output::TimedOut,
+                    };
+                    if events.send(event).is_err() {
+                        return;
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    });
+    sender
+}
+
+/// Time Plugins without Handshake are Given to Exit after their First Reply
+const EXIT_WAIT: Duration = Duration::from_millis(50);
+
+/// Report Plugin as Running unless its Output Closes within [`EXIT_WAIT`]
+///
+/// The reader reports [`Output::Closed`] first when the plugin exits, and
+/// the plugin is then treated as static instead.
+fn spawn_exit_wait(name: String, instance: usize, events: UnboundedSender<PluginEvent>) {
+    std::thread::spawn(move || {
+        std::thread::sleep(EXIT_WAIT);
+        let _ = events.send(PluginEvent {
+            plugin: name,
+            instance,
+            reply: 0,
+            output: Output::Running,
+        });
+    });
+}
+
+/// Number of Plugin Stderr Lines Kept in Memory
+const STDERR_LINES: usize = 100;
+
+/// Shared Ring Buffer of the Latest Plugin Stderr Lines
+type StderrBuffer = Arc<Mutex<VecDeque<String>>>;
+
+/// Log Plugin Stderr on Background Thread while Keeping its Latest Lines
+///
+/// Lines are also written to the debug file when one is given.
+fn spawn_stderr<T: Read + Send + 'static>(
+    name: String,
+    reader: T,
+    buffer: StderrBuffer,
+    mut debug: Option<File>,
+) {
+    std::thread::spawn(move || {
+        for line in BufReader::new(reader).lines().filter_map(|l| l.ok()) {
+            log::warn!("{name:?} stderr: {line}");
+            if let Some(file) = debug.as_mut() {
+                if let Err(err) = writeln!(file, "{line}") {
+                    log::error!("{name:?} failed to write debug file: {err:?}");
+                    debug = None;
+                }
+            }
+            let Ok(mut buffer) = buffer.lock() else {
+                log::error!("{name:?} stderr buffer poisoned");
+                return;
+            };
+            if buffer.len() >= STDERR_LINES {
+                buffer.pop_front();
+            }
+            buffer.push_back(line);
+        }
+    });
+}
+
+/// Create (or Truncate) Plugin Stderr Debug File in the XDG State Directory
+fn debug_file(name: &str) -> Result<File> {
+    let path = xdg::BaseDirectories::with_prefix(XDG_PREFIX)?
+        .place_state_file(format!("plugins/{name}.log"))?;
+    log::info!("{name:?} stderr written to {path:?}");
+    Ok(File::create(path)?)
+}
+
+/// Environment Variable Exposing the Plugin Name to Plugins
+static ENV_PLUGIN: &'static str = "RMENU_PLUGIN";
+
+/// Expand `~` and `${var}` References in Plugin Settings
+///
+/// Variables resolve from the plugin's configured `env` first and then
+/// from `RMENU_*` process environment variables. Unknown variables are
+/// left as-is.
+fn expand(value: &str, env: &BTreeMap<String, String>) -> String {
+    let value = shellexpand::env_with_context_no_errors(value, |var: &str| {
+        env.get(var).cloned().or_else(|| {
+            var.starts_with("RMENU")
+                .then(|| std::env::var(var).ok())
+                .flatten()
+        })
+    });
+    shellexpand::tilde(&value).to_string()
+}
+
 #[derive(Default)]
 pub struct ServerBuilder {
+    debug: bool,
     order: Vec<String>,
     sources: HashMap<String, Source>,
+    history: HashMap<String, History>,
 }
 
 impl ServerBuilder {
+    /// Write Stderr of Subsequently Added Plugins to the XDG State Directory
+    pub fn debug_plugins(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
     pub fn add_input(mut self, format: Format, input: &str) -> Result<Self> {
         let input = if input == "-" { "/dev/stdin" } else { input };
         let input = shellexpand::tilde(input).to_string();
@@ -107,24 +373,39 @@ impl ServerBuilder {
         Ok(self)
     }
 
-    pub fn add_plugin(mut self, name: String, config: &mut Config) -> Result<Self> {
+    pub fn add_plugin(mut self, name: String, config: &Config) -> Result<Self> {
         let cfg = config
             .plugins
             .get(&name)
             .cloned()
             .ok_or_else(|| RMenuError::NoSuchPlugin(name.to_owned()))?;
-        if let Some(options) = cfg.options.as_ref() {
-            config
-                .update(options)
-                .map_err(|e| RMenuError::InvalidKeybind(e))?;
+        if cfg.history {
+            match History::load(&name) {
+                Ok(history) => {
+                    self.history.insert(name.to_owned(), history);
+                }
+                Err(err) => log::error!("{name:?} history unavailable, skipping frecency: {err:?}"),
+            }
         }
-        let plugin = Plugin::new(name.to_owned(), &cfg)?;
+        let builtin = cfg.builtin.as_deref();
+        let source = match builtin.and_then(crate::builtin::get) {
+            Some(plugin) => Source::Native(Native::new(name.to_owned(), plugin)),
+            None => {
+                if let Some(builtin) = builtin {
+                    if cfg.exec.is_empty() {
+                        return Err(RMenuError::NoSuchPlugin(builtin.to_owned()));
+                    }
+                    log::warn!("builtin {builtin:?} not available. using {:?}", cfg.exec);
+                }
+                Source::Plugin(Plugin::new(name.to_owned(), &cfg, self.debug)?)
+            }
+        };
         self.order.push(name.to_owned());
-        self.sources.insert(name.to_owned(), Source::Plugin(plugin));
+        self.sources.insert(name.to_owned(), source);
         Ok(self)
     }
 
-    pub fn add_plugins(mut self, names: Vec<String>, config: &mut Config) -> Result<Self> {
+    pub fn add_plugins(mut self, names: Vec<String>, config: &Config) -> Result<Self> {
         for name in names {
             self = self.add_plugin(name, config)?;
         }
@@ -138,14 +419,20 @@ impl ServerBuilder {
             }
         }
         if show.is_empty() {
-            let mode = self.order.get(0).expect("no active plugins").clone();
+            let mode = self.order.first().ok_or(RMenuError::NoPlugins)?.clone();
             log::warn!("no mode specified. defaulting to {mode:?}");
             show.push(mode);
         }
+        let (sender, receiver) = unbounded_channel();
         return Ok(Server {
             order: self.order,
             sources: self.sources,
+            history: self.history,
             active: show,
+            routed: None,
+            configs: HashMap::new(),
+            sender,
+            receiver: Some(receiver),
         });
     }
 }
@@ -153,27 +440,212 @@ impl ServerBuilder {
 pub struct Server {
     order: Vec<String>,
     active: Vec<String>,
+    /// plugin receiving searches that start with its trigger
+    routed: Option<String>,
+    /// config snapshot for each mode w/ its plugin options applied
+    configs: HashMap<String, Config>,
     sources: HashMap<String, Source>,
+    history: HashMap<String, History>,
+    sender: UnboundedSender<PluginEvent>,
+    receiver: Option<UnboundedReceiver<PluginEvent>>,
 }
 
 impl Server {
-    pub fn search(&mut self, config: &mut Config, query: &str) -> Result<Vec<Entry>> {
-        let mut results = vec![];
-        for name in self.active.iter() {
-            let plugin = self.sources.get_mut(name).expect("plugin missing");
-            let entries = plugin.search(config, query)?;
-            results.extend(entries);
+    /// Send Search to Active Sources and Collect Available Results
+    ///
+    /// Plugins stream their replies in the background, so results
+    /// may be incomplete until their events are handled. A failing
+    /// source does not prevent the remaining sources from searching.
+    pub fn search(&mut self, config: &mut Config, query: &str) -> Result<Vec<SearchResult>> {
+        let snapshot = self.prepare(config, query)?;
+        Ok(self.publish(snapshot.score()))
+    }
+
+    /// Send Search to Active Sources and Capture Snapshot for Scoring
+    ///
+    /// Scoring and builtin searches are left to the snapshot so they may
+    /// run without holding onto the server.
+    pub fn prepare(&mut self, config: &mut Config, query: &str) -> Result<Snapshot> {
+        let query = self.route(config, query);
+        let mut error = None;
+        let mut jobs = HashMap::new();
+        for name in self.targets().to_vec() {
+            let Some(source) = self.sources.get_mut(&name) else {
+                return Err(RMenuError::InvalidPlugin(name));
+            };
+            match source.search(config, query, &self.sender) {
+                Ok(Some(job)) => {
+                    jobs.insert(name, job);
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    log::error!("{name:?} search failed: {err:?}");
+                    error.get_or_insert(err);
+                }
+            }
+        }
+        if let Some(err) = error {
+            return Err(err);
+        }
+        let mut snapshot = self.snapshot(config);
+        for source in snapshot.sources.iter_mut() {
+            source.job = jobs.remove(&source.name);
         }
-        Ok(results)
+        Ok(snapshot)
+    }
+
+    /// Capture Current Entries of Active Sources for Scoring
+    pub fn snapshot(&self, config: &Config) -> Snapshot {
+        let sources = self
+            .targets()
+            .iter()
+            .filter_map(|name| {
+                let source = self.sources.get(name)?;
+                let (search, live, entries) = source.entries();
+                Some(Scoring {
+                    name: name.to_owned(),
+                    search,
+                    live,
+                    entries,
+                    job: None,
+                })
+            })
+            .collect();
+        let history = self
+            .targets()
+            .iter()
+            .filter_map(|name| Some((name.to_owned(), self.history.get(name)?.clone())))
+            .collect();
+        Snapshot {
+            config: config.clone(),
+            sources,
+            history,
+        }
+    }
+
+    /// Store Builtin Entries Loaded while Scoring and Return the Results
+    pub fn publish(&mut self, scored: Scored) -> Vec<SearchResult> {
+        for (name, search, entries) in scored.loaded {
+            if let Some(Source::Native(native)) = self.sources.get_mut(&name) {
+                native.publish(&search, entries);
+            }
+        }
+        scored.results
+    }
+
+    /// Route Searches Starting with a Plugin Trigger to only that Plugin
+    ///
+    /// The routed plugin config is used until its trigger is removed.
+    /// Returns the query with the trigger stripped.
+    fn route<'a>(&mut self, config: &mut Config, query: &'a str) -> &'a str {
+        let routed = self
+            .order
+            .iter()
+            .filter_map(|name| {
+                let trigger = config.plugins.get(name)?.trigger.as_ref()?;
+                let rest = query.strip_prefix(trigger.as_str())?;
+                Some((name, trigger.len(), rest))
+            })
+            .filter(|(_, len, _)| *len > 0)
+            .max_by_key(|(_, len, _)| *len)
+            .map(|(name, _, rest)| (name.to_owned(), rest));
+        let name = routed.as_ref().map(|(name, _)| name.to_owned());
+        if name != self.routed {
+            if let Some(name) = name.as_ref() {
+                log::info!("routing searches to {name:?}");
+            }
+            self.routed = name;
+            if let Some(mode) = self.mode_config() {
+                *config = mode.clone();
+            }
+        }
+        routed.map(|(_, rest)| rest).unwrap_or(query)
+    }
+
+    /// Update Config Snapshots for each Mode
+    #[inline]
+    pub fn set_configs(&mut self, configs: HashMap<String, Config>) {
+        self.configs = configs;
+    }
+
+    /// Config Snapshot for the Plugin Receiving the Current Search
+    pub fn mode_config(&self) -> Option<&Config> {
+        let mode = self.routed.as_deref().or_else(|| self.mode())?;
+        self.configs.get(mode)
+    }
+
+    /// Plugins Receiving the Current Search
+    fn targets(&self) -> &[String] {
+        match self.routed.as_ref() {
+            Some(name) => std::slice::from_ref(name),
+            None => &self.active,
+        }
+    }
+
+    /// Take Receiver for Streamed Plugin Events
+    #[inline]
+    pub fn take_events(&mut self) -> Option<UnboundedReceiver<PluginEvent>> {
+        self.receiver.take()
+    }
+
+    /// Handle Streamed Plugin Event
+    ///
+    /// Returns true when the results of an active source have changed.
+    pub fn handle_event(&mut self, config: &mut Config, event: PluginEvent) -> Result<bool> {
+        let Some(Source::Plugin(plugin)) = self.sources.get_mut(&event.plugin) else {
+            return Ok(false);
+        };
+        if event.instance != plugin.instance {
+            log::debug!("discarding event from previous {:?} instance", event.plugin);
+            return Ok(false);
+        }
+        // keep runtime plugin options when switching back to its mode
+        if let Output::Message(Message::Options(options)) = &event.output {
+            if let Some(snapshot) = self.configs.get_mut(&event.plugin) {
+                snapshot
+                    .update(options)
+                    .map_err(|s| RMenuError::InvalidKeybind(s))?;
+            }
+        }
+        let changed = plugin.handle(config, event.reply, event.output)?;
+        Ok(changed && self.targets().contains(&event.plugin))
+    }
+
+    /// Collect Current Results from Active Sources Ranked by Score
+    pub fn results(&self, config: &Config) -> Vec<SearchResult> {
+        self.snapshot(config).score().results
+    }
+
+    /// Record Selected Result in its Plugin History (if Enabled)
+    pub fn record(&mut self, result: &SearchResult) {
+        if let Some(history) = self.history.get_mut(&result.source) {
+            if let Err(err) = history.record(&result.entry) {
+                log::error!("history write error: {err:?}");
+            }
+        }
+    }
+
+    /// Update Active Plugins (Defaults to First when Empty)
+    pub fn set_active(&mut self, mut show: Vec<String>) -> Result<()> {
+        for name in show.iter() {
+            if !self.sources.contains_key(name) {
+                return Err(RMenuError::InvalidPlugin(name.to_owned()));
+            }
+        }
+        if show.is_empty() {
+            show.push(self.order.first().ok_or(RMenuError::NoPlugins)?.clone());
+        }
+        log::info!("switching active plugins: {show:?}");
+        self.active = show;
+        Ok(())
     }
 
     pub fn placeholder(&self, config: &Config) -> String {
-        let mode = self.active.last().expect("no active plugins");
-        let plugin = config
-            .plugins
-            .get(mode)
-            .map(|c| c.placeholder.clone())
-            .unwrap_or_default();
+        let plugin = self
+            .targets()
+            .last()
+            .and_then(|mode| config.plugins.get(mode))
+            .and_then(|c| c.placeholder.clone());
         config
             .search
             .placeholder
@@ -182,43 +654,74 @@ impl Server {
             .unwrap_or_default()
     }
 
-    fn mode_index(&self) -> usize {
-        let mode = self.active.last().expect("no active plugins");
+    /// Available Plugin Modes in Order
+    #[inline]
+    pub fn modes(&self) -> &[String] {
+        &self.order
+    }
+
+    /// Check if the Specified Plugin is Active
+    #[inline]
+    pub fn is_active(&self, name: &str) -> bool {
+        self.active.iter().any(|a| a == name)
+    }
+
+    /// Current Mode (Last Active Plugin)
+    #[inline]
+    pub fn mode(&self) -> Option<&str> {
+        self.active.last().map(String::as_str)
+    }
+
+    /// Switch to the Specified Plugin Mode
+    pub fn set_mode(&mut self, mode: &str) -> Result<()> {
+        if !self.sources.contains_key(mode) {
+            return Err(RMenuError::InvalidPlugin(mode.to_owned()));
+        }
+        log::info!("switching to mode: {mode:?}");
+        self.active = vec![mode.to_owned()];
+        Ok(())
+    }
+
+    /// Position of the Current Mode within the Plugin Order
+    fn mode_index(&self) -> Result<usize> {
+        let mode = self.mode().ok_or(RMenuError::NoPlugins)?;
         self.order
             .iter()
             .position(|m| m == mode)
-            .expect("invalid mode")
+            .ok_or_else(|| RMenuError::InvalidPlugin(mode.to_owned()))
     }
 
-    pub fn next_plugin(&mut self) {
-        let index = self.mode_index();
-        let mode = match index == self.order.len() - 1 {
-            true => self.order.first().expect("no plugins avaialble"),
-            false => self.order.get(index + 1).expect("cannot find next plugin"),
-        };
+    /// Switch to the Next Plugin Mode (Wrapping Around)
+    pub fn next_plugin(&mut self) -> Result<()> {
+        let index = self.mode_index()?;
+        let mode = self.order[(index + 1) % self.order.len()].clone();
         log::info!("switching to next mode: {mode:?}");
-        self.active = vec![mode.to_owned()];
+        self.active = vec![mode];
+        Ok(())
     }
 
-    pub fn prev_plugin(&mut self) {
-        let index = self.mode_index();
-        let mode = match index == 0 {
-            true => self.order.last().expect("no plugins avaialble"),
-            false => self.order.get(index - 1).expect("cannot find prev plugin"),
-        };
+    /// Switch to the Previous Plugin Mode (Wrapping Around)
+    pub fn prev_plugin(&mut self) -> Result<()> {
+        let index = self.mode_index()?;
+        let mode = self.order[(index + self.order.len() - 1) % self.order.len()].clone();
         log::info!("switching to prev mode: {mode:?}");
-        self.active = vec![mode.to_owned()];
+        self.active = vec![mode];
+        Ok(())
     }
 
     pub fn cleanup(&mut self) {
         let mut threads = vec![];
         for source in self.sources.values_mut() {
-            if let Source::Plugin(plugin) = source {
-                if let Some(thread) = plugin.cache_thread.take() {
-                    threads.push(thread);
-                }
-                if let Err(err) = plugin.command.kill() {
-                    log::warn!("failed to kill {:?} {:?}", plugin.name, err);
+            match source {
+                Source::Input(_) => {}
+                Source::Native(native) => native.stop(),
+                Source::Plugin(plugin) => {
+                    if let Some(thread) = plugin.cache_thread.take() {
+                        threads.push(thread);
+                    }
+                    if let Err(err) = plugin.command.kill() {
+                        log::warn!("failed to kill {:?} {:?}", plugin.name, err);
+                    }
                 }
             }
         }
@@ -230,16 +733,109 @@ impl Server {
     }
 }
 
+/// Entries of a Single Source Captured for Scoring
+struct Scoring {
+    name: String,
+    search: Search,
+    live: bool,
+    entries: Arc<Vec<Entry>>,
+    /// builtin search to complete before scoring
+    job: Option<NativeSearch>,
+}
+
+/// Search State Captured for Scoring Results off the GUI Thread
+pub struct Snapshot {
+    config: Config,
+    sources: Vec<Scoring>,
+    history: HashMap<String, History>,
+}
+
+/// Results Scored from a Snapshot and the Builtin Entries it Loaded
+#[derive(Default)]
+pub struct Scored {
+    results: Vec<SearchResult>,
+    loaded: Vec<(String, Search, Arc<Vec<Entry>>)>,
+}
+
+impl Snapshot {
+    /// Complete Builtin Searches and Rank Results by Score
+    ///
+    /// Results are kept together in plugin order when grouping is enabled.
+    pub fn score(self) -> Scored {
+        let config = &self.config;
+        let mut results = vec![];
+        let mut loaded = vec![];
+        for mut source in self.sources {
+            if let Some(job) = source.job.take() {
+                source.entries = Arc::new(job.run(&source.search));
+                loaded.push((
+                    source.name.to_owned(),
+                    source.search.clone(),
+                    source.entries.clone(),
+                ));
+            }
+            let mut entries = match source.live {
+                true => live_search(config, &source.search, &source.entries),
+                false => memory_search(config, &source.search, &source.entries),
+            };
+            let history = self.history.get(&source.name);
+            for result in entries.iter_mut() {
+                result.source = source.name.to_owned();
+                if let Some(history) = history {
+                    result.matches.score += history.score(&result.entry);
+                }
+            }
+            if config.group_results {
+                entries.sort_by(|a, b| b.matches.score.cmp(&a.matches.score));
+            }
+            results.extend(entries);
+        }
+        // stable sort to retain plugin order on equal scores
+        if !config.group_results {
+            results.sort_by(|a, b| b.matches.score.cmp(&a.matches.score));
+        }
+        Scored { results, loaded }
+    }
+}
+
 enum Source {
     Input(Input),
+    Native(Native),
     Plugin(Plugin),
 }
 
 impl Source {
-    pub fn search(&mut self, config: &mut Config, query: &str) -> Result<Vec<Entry>> {
+    pub fn search(
+        &mut self,
+        config: &mut Config,
+        query: &str,
+        events: &UnboundedSender<PluginEvent>,
+    ) -> Result<Option<NativeSearch>> {
+        match self {
+            Self::Input(input) => input.search(config, query).map(|_| None),
+            Self::Native(native) => native.search(config, query),
+            Self::Plugin(plugin) => plugin.search(config, query, events).map(|_| None),
+        }
+    }
+
+    /// Current Search, Search Mode and Entries of the Source
+    pub fn entries(&self) -> (Search, bool, Arc<Vec<Entry>>) {
         match self {
-            Self::Input(input) => input.search(config, query),
-            Self::Plugin(plugin) => plugin.search(config, query),
+            Self::Input(input) => (
+                input.search.clone(),
+                false,
+                input.results.clone().unwrap_or_default(),
+            ),
+            Self::Native(native) => (
+                native.search.clone(),
+                native.live,
+                native.results.clone().unwrap_or_default(),
+            ),
+            Self::Plugin(plugin) => (
+                plugin.search.clone(),
+                plugin.mode == Mode::Live,
+                plugin.results.clone(),
+            ),
         }
     }
 }
@@ -247,7 +843,8 @@ impl Source {
 struct Input {
     input: PathBuf,
     format: Format,
-    results: Option<Vec<Entry>>,
+    search: Search,
+    results: Option<Arc<Vec<Entry>>>,
 }
 
 impl Input {
@@ -255,81 +852,372 @@ impl Input {
         Ok(Self {
             input,
             format,
+            search: new_search("", &Config::default()),
             results: None,
         })
     }
 
-    pub fn search(&mut self, config: &mut Config, query: &str) -> Result<Vec<Entry>> {
-        let search = new_search(query, &config);
-        let entries = match self.results.as_ref() {
-            Some(results) => results.clone(),
-            None => {
-                log::info!("reading from: {:?}", self.input);
-                let path = File::open(&self.input)?;
-                let reader = BufReader::new(&path);
-                let entries = read_entries(&self.format, config, reader)?;
-                self.results = Some(entries.clone());
-                entries
+    pub fn search(&mut self, config: &mut Config, query: &str) -> Result<()> {
+        self.search = new_search(query, &config);
+        if self.results.is_none() {
+            log::info!("reading from: {:?}", self.input);
+            let path = File::open(&self.input)?;
+            let reader = BufReader::new(&path);
+            let entries = read_entries(&self.format, config, reader)?;
+            self.results = Some(Arc::new(entries));
+        }
+        Ok(())
+    }
+}
+
+/// Filter and Score Entries in Memory
+fn memory_search(config: &Config, search: &Search, entries: &[Entry]) -> Vec<SearchResult> {
+    let filter = new_searchfn(&search, &config.search.mode);
+    entries
+        .iter()
+        .filter_map(|e| filter(e).map(|m| SearchResult::new(e.clone(), m)))
+        .collect()
+}
+
+/// Score Live Results for Ranking but Keep Everything the Plugin Returned
+fn live_search(config: &Config, search: &Search, entries: &[Entry]) -> Vec<SearchResult> {
+    let filter = new_searchfn(&search, &config.search.mode);
+    entries
+        .iter()
+        .map(|e| SearchResult::new(e.clone(), filter(e).unwrap_or_default()))
+        .collect()
+}
+
+/// In-Process Plugin Compiled into RMenu
+struct Native {
+    name: String,
+    builtin: Arc<Mutex<Builtin>>,
+    live: bool,
+    search: Search,
+    results: Option<Arc<Vec<Entry>>>,
+}
+
+/// Builtin Plugin State Shared with Search Workers
+struct Builtin {
+    plugin: Box<dyn rmenu_plugin::Plugin>,
+    started: bool,
+    /// entries listed by static plugins
+    listed: Option<Arc<Vec<Entry>>>,
+}
+
+/// Builtin Search Run while Scoring a Snapshot
+struct NativeSearch {
+    name: String,
+    live: bool,
+    config: PluginConfig,
+    builtin: Arc<Mutex<Builtin>>,
+}
+
+impl NativeSearch {
+    /// Search Builtin Plugin (Starting it if Required)
+    ///
+    /// Static plugins are only searched once and their entries cached.
+    fn run(&self, search: &Search) -> Vec<Entry> {
+        let mut builtin = self.builtin.lock().expect("builtin lock poisoned");
+        if let Some(listed) = builtin.listed.as_ref() {
+            return listed.to_vec();
+        }
+        if !builtin.started {
+            log::debug!("starting builtin {:?}", self.name);
+            builtin.plugin.start();
+            builtin.started = true;
+        }
+        log::debug!("builtin {:?} searching {search:?}", self.name);
+        let entries = builtin.plugin.search(search);
+        if !self.live {
+            if let Err(err) = crate::cache::write_cache(&self.name, &self.config, &entries) {
+                log::error!("cache write error: {err:?}");
             }
-        };
-        let filter = new_searchfn(&search);
-        Ok(entries.into_iter().filter(|e| filter(e)).collect())
+            builtin.listed = Some(Arc::new(entries.clone()));
+        }
+        entries
+    }
+}
+
+impl Native {
+    pub fn new(name: String, plugin: Box<dyn rmenu_plugin::Plugin>) -> Self {
+        let live = plugin.hello().live;
+        Self {
+            name,
+            builtin: Arc::new(Mutex::new(Builtin {
+                plugin,
+                started: false,
+                listed: None,
+            })),
+            live,
+            search: new_search("", &Config::default()),
+            results: None,
+        }
+    }
+
+    /// Update Search and Return Builtin Search to Run (if Required)
+    pub fn search(&mut self, config: &mut Config, query: &str) -> Result<Option<NativeSearch>> {
+        self.search = new_search(query, &config);
+        if !self.live && self.results.is_some() {
+            return Ok(None);
+        }
+        let plugin = config
+            .plugins
+            .get(&self.name)
+            .ok_or_else(|| RMenuError::NoSuchPlugin(self.name.to_owned()))?;
+        // static entries are cached the same as plugin processes
+        if !self.live {
+            match crate::cache::read_cache(&self.name, &plugin) {
+                Err(err) => log::error!("cache read failed: {err:?}"),
+                Ok(cached) => {
+                    log::info!(
+                        "{:?} entries read from cache for builtin {:?}",
+                        cached.len(),
+                        self.name
+                    );
+                    self.results = Some(Arc::new(cached));
+                    return Ok(None);
+                }
+            }
+        }
+        Ok(Some(NativeSearch {
+            name: self.name.to_owned(),
+            live: self.live,
+            config: plugin.clone(),
+            builtin: self.builtin.clone(),
+        }))
+    }
+
+    /// Store Entries Found by a Builtin Search unless Since Replaced
+    pub fn publish(&mut self, search: &Search, entries: Arc<Vec<Entry>>) {
+        if self.live && search.search != self.search.search {
+            return;
+        }
+        self.results = Some(entries);
     }
+
+    /// Stop Plugin (if Started)
+    pub fn stop(&mut self) {
+        let mut builtin = self.builtin.lock().expect("builtin lock poisoned");
+        if builtin.started {
+            builtin.plugin.stop();
+            builtin.started = false;
+        }
+    }
+}
+
+/// Plugin Reply Behavior Declared on Startup or Discovered from First Reply
+#[derive(Debug, PartialEq)]
+enum Mode {
+    /// no handshake received and first reply has not completed yet
+    Unknown,
+    /// plugin lists all entries once and results are searched in memory
+    Static,
+    /// plugin replies to every search sent over stdin
+    Live,
 }
 
 struct Plugin {
     name: String,
     args: Vec<String>,
+    env: BTreeMap<String, String>,
+    cwd: Option<String>,
     format: Format,
-    results: Option<Vec<Entry>>,
+    mode: Mode,
+    search: Search,
+    initial: Option<String>,
+    pending: bool,
+    replied: bool,
+    sent: u64,
+    done: u64,
+    results: Arc<Vec<Entry>>,
     command: Cmd,
-    cache_thread: Option<std::thread::JoinHandle<()>>,
+    events: Option<UnboundedSender<PluginEvent>>,
+    instance: usize,
+    timeout: Option<Duration>,
+    timer: Option<Sender<u64>>,
+    restart: RestartPolicy,
+    restarts: usize,
+    max_restarts: usize,
+    stderr: StderrBuffer,
+    debug: bool,
+    cache_thread: Option<JoinHandle<()>>,
 }
 
 impl Plugin {
-    pub fn new(name: String, config: &PluginConfig) -> Result<Self> {
-        let args: Vec<String> = config
-            .exec
-            .iter()
-            .map(|s| shellexpand::tilde(s).to_string())
-            .collect();
+    pub fn new(name: String, config: &PluginConfig, debug: bool) -> Result<Self> {
+        let mut env = config.env.clone();
+        env.insert(ENV_PLUGIN.to_owned(), name.to_owned());
         Ok(Self {
             name,
-            args,
+            args: config.exec.clone(),
+            env,
+            cwd: config.cwd.clone(),
             format: config.format.clone(),
-            results: None,
+            mode: Mode::Unknown,
+            search: new_search("", &Config::default()),
+            initial: None,
+            pending: false,
+            replied: false,
+            sent: 0,
+            done: 0,
+            results: Default::default(),
             command: Cmd::NotStarted,
+            events: None,
+            instance: 0,
+            timeout: config.timeout_ms.map(Duration::from_millis),
+            timer: None,
+            restart: config.restart,
+            restarts: 0,
+            max_restarts: config.max_restarts,
+            stderr: Default::default(),
+            debug,
             cache_thread: None,
         })
     }
 
-    fn read(&mut self, config: &mut Config) -> Result<()> {
-        let command = self.command.child();
-        let stdout = command
-            .stdout
+    /// Send Latest Search Query to Plugin Stdin
+    fn send(&mut self) -> Result<()> {
+        log::debug!("sending search message to plugin {:?}", self.name);
+        self.search.id = Some(self.sent + 1);
+        let mut message = serde_json::to_vec(&self.search)?;
+        message.push(b'\n');
+        let stdin = self
+            .command
+            .child()
+            .stdin
             .as_mut()
             .ok_or_else(|| RMenuError::CommandError(None))?;
-        let reader = BufReader::new(stdout);
-        let entries = read_entries(&self.format, config, reader)?;
-        self.results = Some(entries);
+        match stdin.write_all(&message) {
+            Ok(_) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => {
+                log::debug!("plugin {:?} closed stdin", self.name);
+                self.mode = Mode::Static;
+                return Ok(());
+            }
+            Err(err) => return Err(err.into()),
+        }
+        self.initial
+            .get_or_insert_with(|| self.search.search.clone());
+        self.results = Default::default();
+        self.sent += 1;
+        if let Some(timer) = self.timer.as_ref() {
+            let _ = timer.send(self.sent);
+        }
+        Ok(())
+    }
+
+    /// Spawn Plugin Process and Background Output Reader
+    fn spawn(&mut self, events: &UnboundedSender<PluginEvent>) -> Result<()> {
+        // expand settings on spawn so `RMENU_*` variables are up to date
+        let env: BTreeMap<String, String> = self
+            .env
+            .iter()
+            .map(|(k, v)| (k.to_owned(), expand(v, &self.env)))
+            .collect();
+        let args: Vec<String> = self.args.iter().map(|a| expand(a, &env)).collect();
+        let main = args
+            .get(0)
+            .ok_or_else(|| RMenuError::InvalidPlugin(self.name.to_owned()))?;
+        let mut command = Command::new(main);
+        if let Some(cwd) = self.cwd.as_ref() {
+            command.current_dir(expand(cwd, &env));
+        }
+        log::debug!("spawning plugin {:?} {args:?}", self.name);
+        let mut child = command
+            .args(&args[1..])
+            .envs(&env)
+            .env(PROTOCOL_ENV, PROTOCOL_VERSION.to_string())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| self.failed(err.to_string()))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| RMenuError::CommandError(None))?;
+        if let Some(stderr) = child.stderr.take() {
+            let debug = match self.debug {
+                true => debug_file(&self.name)
+                    .map_err(|err| log::error!("{:?} debug file error: {err:?}", self.name))
+                    .ok(),
+                false => None,
+            };
+            spawn_stderr(self.name.to_owned(), stderr, self.stderr.clone(), debug);
+        }
+        let name = self.name.to_owned();
+        spawn_reader(name, self.instance, self.format, stdout, events.clone());
+        self.timer = self.timeout.map(|timeout| {
+            let name = self.name.to_owned();
+            spawn_timer(name, self.instance, timeout, events.clone())
+        });
+        self.command = Cmd::Started(child);
+        self.events = Some(events.clone());
         Ok(())
     }
 
-    pub fn memory_search(&mut self, search: &Search) -> Result<Vec<Entry>> {
-        let results = self.results.as_ref().expect("results should be set");
-        let filter = new_searchfn(&search);
-        return Ok(results.iter().filter(|e| filter(e)).cloned().collect());
+    /// Kill and Respawn Plugin (Returns False once Out of Restarts)
+    fn respawn(&mut self) -> Result<bool> {
+        if self.restarts >= self.max_restarts {
+            log::warn!("plugin {:?} exceeded max restarts", self.name);
+            return Ok(false);
+        }
+        let Some(events) = self.events.clone() else {
+            return Ok(false);
+        };
+        self.stop();
+        self.restarts += 1;
+        log::warn!(
+            "restarting plugin {:?} ({}/{})",
+            self.name,
+            self.restarts,
+            self.max_restarts
+        );
+        self.mode = Mode::Unknown;
+        self.pending = false;
+        self.sent = 0;
+        self.done = 0;
+        self.spawn(&events)?;
+        self.send()?;
+        Ok(true)
+    }
+
+    /// Kill Plugin Process (if Running) and Ignore its Remaining Output
+    fn stop(&mut self) {
+        if let Cmd::Started(child) = &mut self.command {
+            if let Err(err) = child.kill() {
+                log::warn!("failed to kill {:?} {:?}", self.name, err);
+            }
+            let _ = child.wait();
+        }
+        self.command = Cmd::Skipped;
+        self.timer = None;
+        self.instance += 1;
     }
 
-    pub fn write_cache(&mut self, config: &mut Config, query: &str) {
-        if query.is_empty() {
-            let plugin = config
-                .plugins
-                .get(&self.name)
-                .cloned()
-                .expect("missing plugin config");
+    /// Build Plugin Failure Error including the Latest Stderr Output
+    fn failed(&self, reason: String) -> RMenuError {
+        let stderr = self
+            .stderr
+            .lock()
+            .map(|tail| tail.iter().cloned().collect())
+            .unwrap_or_default();
+        RMenuError::PluginFailed {
+            plugin: self.name.to_owned(),
+            reason,
+            stderr,
+        }
+    }
+
+    pub fn write_cache(&mut self, config: &Config) {
+        if self.initial.as_ref().is_some_and(|q| q.is_empty()) {
+            let Some(plugin) = config.plugins.get(&self.name).cloned() else {
+                log::error!("{:?} cache write skipped: missing plugin config", self.name);
+                return;
+            };
             let name = self.name.to_owned();
-            let results = self.results.clone().expect("results should be set");
+            let results = self.results.clone();
             self.cache_thread = Some(std::thread::spawn(move || match crate::cache::write_cache(
                 &name, &plugin, &results,
             ) {
@@ -339,77 +1227,222 @@ impl Plugin {
         }
     }
 
-    pub fn search(&mut self, config: &mut Config, query: &str) -> Result<Vec<Entry>> {
-        let search = new_search(query, &config);
+    pub fn search(
+        &mut self,
+        config: &mut Config,
+        query: &str,
+        events: &UnboundedSender<PluginEvent>,
+    ) -> Result<()> {
+        self.search = new_search(query, &config);
+        log::debug!("plugin {:?} searching {:?}", self.name, self.search);
         match self.command {
-            Cmd::Started(_) => {}
-            Cmd::Skipped => return self.memory_search(&search),
+            Cmd::Skipped => Ok(()),
             Cmd::NotStarted => {
                 // check cache if not already loaded
-                if self.results.is_none() {
-                    let plugin = config
-                        .plugins
-                        .get(&self.name)
-                        .expect("missing plugin config");
-                    match crate::cache::read_cache(&self.name, &plugin) {
-                        Err(err) => log::error!("cache read failed: {err:?}"),
-                        Ok(cached) => {
-                            log::info!(
-                                "{:?} entries read from cache for plugin {:?}",
-                                cached.len(),
-                                self.name
-                            );
-                            self.command = Cmd::Skipped;
-                            self.results = Some(cached);
-                            return self.memory_search(&search);
-                        }
+                let plugin = config
+                    .plugins
+                    .get(&self.name)
+                    .ok_or_else(|| RMenuError::NoSuchPlugin(self.name.to_owned()))?;
+                match crate::cache::read_cache(&self.name, &plugin) {
+                    Err(err) => log::error!("cache read failed: {err:?}"),
+                    Ok(cached) => {
+                        log::info!(
+                            "{:?} entries read from cache for plugin {:?}",
+                            cached.len(),
+                            self.name
+                        );
+                        self.command = Cmd::Skipped;
+                        self.mode = Mode::Static;
+                        self.results = Arc::new(cached);
+                        return Ok(());
                     }
                 }
-                // spawn command for later processing
-                let main = self
-                    .args
-                    .get(0)
-                    .ok_or_else(|| RMenuError::InvalidPlugin(self.name.to_owned()))?;
-                self.command = Cmd::Started(
-                    Command::new(main)
-                        .args(&self.args[1..])
-                        .stdin(Stdio::piped())
-                        .stdout(Stdio::piped())
-                        .spawn()?,
-                );
+                // spawn command and send initial search
+                self.spawn(events)?;
+                self.send()
             }
+            Cmd::Started(_) => match self.mode {
+                Mode::Static => Ok(()),
+                Mode::Live => self.send(),
+                Mode::Unknown => {
+                    // wait for handshake or first reply before deciding how to search
+                    self.pending = true;
+                    Ok(())
+                }
+            },
         }
-        // handle case where command has exited and returned final results
-        log::debug!("plugin {:?} searching {search:?}", self.name);
-        let command = self.command.child();
-        let status = command.try_wait()?;
-        if let Some(status) = status {
-            if !status.success() {
-                return Err(RMenuError::CommandError(Some(status)));
+    }
+
+    /// Handle Message Streamed from Plugin Output
+    ///
+    /// Returns true when the current results have changed.
+    pub fn handle(&mut self, config: &mut Config, reply: u64, output: Output) -> Result<bool> {
+        // discard replies to searches that have since been replaced
+        let current = reply >= self.sent || self.mode != Mode::Live;
+        let message = match output {
+            Output::Message(message) => message,
+            Output::Closed => return self.closed(config),
+            Output::TimedOut => return self.timed_out(reply),
+            Output::Running => return self.running(),
+        };
+        match message {
+            Message::Hello(hello) => {
+                log::debug!("plugin {:?} capabilities {hello:?}", self.name);
+                if hello.version > PROTOCOL_VERSION {
+                    log::warn!(
+                        "plugin {:?} protocol v{} is newer than v{PROTOCOL_VERSION}",
+                        self.name,
+                        hello.version
+                    );
+                }
+                if self.mode != Mode::Unknown {
+                    return Ok(false);
+                }
+                self.mode = match hello.live {
+                    true => Mode::Live,
+                    false => Mode::Static,
+                };
+                log::debug!("plugin {:?} running in {:?} mode", self.name, self.mode);
+                if self.mode == Mode::Live && self.pending {
+                    self.pending = false;
+                    self.send()?;
+                    return Ok(true);
+                }
+                Ok(false)
             }
-            if self.results.is_none() {
-                self.read(config)?;
-                self.write_cache(config, query);
+            Message::Entry(entry) => {
+                if current {
+                    Arc::make_mut(&mut self.results).push(entry);
+                }
+                Ok(current)
+            }
+            Message::Batch { entries, .. } => {
+                if current {
+                    Arc::make_mut(&mut self.results).extend(entries);
+                }
+                Ok(current)
+            }
+            Message::Options(options) => {
+                config
+                    .update(&options)
+                    .map_err(|s| RMenuError::InvalidKeybind(s))?;
+                Ok(current)
+            }
+            Message::Stop { .. } => {
+                self.done = self.done.max(reply);
+                // only count consecutive failures against the restart limit
+                self.restarts = 0;
+                if !self.replied {
+                    self.replied = true;
+                    self.write_cache(config);
+                }
+                // fallback for plugins that predate the handshake: static
+                // plugins exit after their first reply while live ones do not
+                if self.mode == Mode::Unknown {
+                    if let Some(events) = self.events.as_ref() {
+                        spawn_exit_wait(self.name.to_owned(), self.instance, events.clone());
+                    }
+                }
+                Ok(false)
             }
-            return self.memory_search(&search);
         }
-        // send search message to program
-        log::debug!("sending search message to plugin {:?}", self.name);
-        let mut message = serde_json::to_vec(&search)?;
-        message.push(b'\n');
-        let stdin = command
-            .stdin
-            .as_mut()
-            .ok_or_else(|| RMenuError::CommandError(None))?;
-        stdin.write(&message)?;
-        // read response and return results
-        log::debug!("reading replies from plugin {:?}", self.name);
-        self.read(config)?;
-        self.write_cache(config, query);
-        Ok(self
-            .results
-            .as_ref()
-            .expect("results should be set")
-            .clone())
+    }
+
+    /// Switch Plugin without Handshake to Live Mode once Known to Keep Running
+    fn running(&mut self) -> Result<bool> {
+        if self.mode != Mode::Unknown {
+            return Ok(false);
+        }
+        self.mode = Mode::Live;
+        log::debug!("plugin {:?} running in {:?} mode", self.name, self.mode);
+        if self.pending {
+            self.pending = false;
+            self.send()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Handle Plugin Output Closing and Restart According to Policy
+    fn closed(&mut self, config: &mut Config) -> Result<bool> {
+        let Cmd::Started(child) = &mut self.command else {
+            return Ok(false);
+        };
+        let status = child.wait()?;
+        self.done = self.sent;
+        if !self.replied {
+            self.replied = true;
+            self.write_cache(config);
+        }
+        let live = self.mode == Mode::Live;
+        if self.mode == Mode::Unknown {
+            log::debug!("plugin {:?} running in {:?} mode", self.name, Mode::Static);
+        }
+        self.mode = Mode::Static;
+        // static plugins are expected to exit once their entries are listed
+        let restart = match self.restart {
+            RestartPolicy::Never => false,
+            RestartPolicy::OnFailure => !status.success(),
+            RestartPolicy::Always => live || !status.success(),
+        };
+        if restart && self.respawn()? {
+            return Ok(true);
+        }
+        if !status.success() {
+            return Err(self.failed(status.to_string()));
+        }
+        Ok(true)
+    }
+
+    /// Kill Plugin that Failed to Reply in Time (Restarting if Allowed)
+    fn timed_out(&mut self, reply: u64) -> Result<bool> {
+        // ignore timers for replies that completed or were replaced
+        if reply != self.sent || self.done >= reply {
+            return Ok(false);
+        }
+        let err = RMenuError::PluginTimeout {
+            plugin: self.name.to_owned(),
+            timeout: self.timeout.unwrap_or_default(),
+        };
+        log::error!("{err}");
+        if self.restart != RestartPolicy::Never && self.respawn()? {
+            return Err(err);
+        }
+        self.stop();
+        self.mode = Mode::Static;
+        Err(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_prefers_plugin_env() {
+        let env = BTreeMap::from([("NAME".to_owned(), "value".to_owned())]);
+        assert_eq!(expand("$NAME/${NAME}", &env), "value/value");
+    }
+
+    #[test]
+    fn expand_leaves_unknown_vars() {
+        let env = BTreeMap::new();
+        assert_eq!(
+            expand("$RMENU_TEST_UNSET_VAR", &env),
+            "$RMENU_TEST_UNSET_VAR"
+        );
+        assert_eq!(expand("$HOME_NOT_RMENU", &env), "$HOME_NOT_RMENU");
+    }
+
+    #[test]
+    fn expand_rmenu_process_env() {
+        std::env::set_var("RMENU_TEST_EXPAND", "set");
+        assert_eq!(expand("$RMENU_TEST_EXPAND", &BTreeMap::new()), "set");
+    }
+
+    #[test]
+    fn expand_tilde() {
+        let home = shellexpand::tilde("~").to_string();
+        assert_eq!(expand("~/bin", &BTreeMap::new()), format!("{home}/bin"));
     }
 }
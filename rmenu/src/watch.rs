@@ -0,0 +1,180 @@
+//! File Watcher for Hot-Reloading Config and Stylesheets
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+use notify::event::{AccessKind, AccessMode};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+use crate::cli::Args;
+use crate::config::Config;
+use crate::server::Result;
+
+/// Time to Wait for Further Events from the Same Save
+static DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Change Observed by the File Watcher
+#[derive(Debug)]
+pub enum Reload {
+    /// config was modified and each mode snapshot rebuilt
+    Config(HashMap<String, Config>),
+    /// css or theme stylesheet was modified
+    Css,
+}
+
+/// Expand Filepath into Itself and its Symlink Target
+fn targets(path: &str) -> Vec<PathBuf> {
+    let path = PathBuf::from(shellexpand::tilde(path).to_string());
+    let mut targets = vec![path.clone()];
+    if let Ok(real) = path.canonicalize() {
+        if real != path {
+            targets.push(real);
+        }
+    }
+    targets
+}
+
+/// Collect Theme and Stylesheets Used by any Mode Snapshot
+fn css_files(cli: &Args, configs: &HashMap<String, Config>) -> Vec<PathBuf> {
+    let mut files = vec![cli.get_theme(), cli.css.clone()];
+    files.extend(configs.values().map(|c| c.css.clone()));
+    files
+        .into_iter()
+        .flatten()
+        .flat_map(|p| targets(&p))
+        .collect()
+}
+
+/// Watcher over Parent Directories of the Tracked Files
+///
+/// Directories are watched instead of files so editors that save by
+/// replacing the file are still observed.
+struct Watch {
+    watcher: notify::RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    dirs: HashSet<PathBuf>,
+}
+
+impl Watch {
+    fn new() -> notify::Result<Self> {
+        let (sender, events) = channel();
+        let watcher = notify::recommended_watcher(sender)?;
+        Ok(Self {
+            watcher,
+            events,
+            dirs: HashSet::new(),
+        })
+    }
+
+    /// Watch Parent Directory of each File not Already Watched
+    fn add_files(&mut self, files: &[PathBuf]) -> notify::Result<()> {
+        for file in files {
+            let Some(dir) = file.parent().filter(|d| d.is_dir()) else {
+                continue;
+            };
+            if self.dirs.contains(dir) {
+                continue;
+            }
+            log::info!("watching {file:?} for changes");
+            self.watcher.watch(dir, RecursiveMode::NonRecursive)?;
+            self.dirs.insert(dir.to_owned());
+        }
+        Ok(())
+    }
+
+    /// Wait for Changed Paths until Timeout (Blocks Forever on None)
+    fn read(&self, timeout: Option<Duration>) -> notify::Result<Vec<PathBuf>> {
+        let event = match timeout {
+            Some(timeout) => match self.events.recv_timeout(timeout) {
+                Ok(event) => event,
+                Err(RecvTimeoutError::Timeout) => return Ok(vec![]),
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(notify::Error::generic("watcher disconnected"))
+                }
+            },
+            None => self
+                .events
+                .recv()
+                .map_err(|_| notify::Error::generic("watcher disconnected"))?,
+        }?;
+        let written = matches!(
+            event.kind,
+            EventKind::Create(_)
+                | EventKind::Modify(_)
+                | EventKind::Access(AccessKind::Close(AccessMode::Write))
+        );
+        match written {
+            true => Ok(event.paths),
+            false => Ok(vec![]),
+        }
+    }
+}
+
+/// Check if Any Changed Path is a Tracked File
+fn modified(changed: &[PathBuf], files: &[PathBuf]) -> bool {
+    changed.iter().any(|p| files.contains(p))
+}
+
+/// Watch Config and Stylesheets and Forward Reloads over Channel
+///
+/// Modes keep the plugins they were started with, so only config
+/// snapshots are rebuilt when the config changes.
+pub fn listen(
+    cli: Args,
+    configs: &HashMap<String, Config>,
+    modes: Vec<String>,
+    env_plugins: Vec<String>,
+) -> Result<UnboundedReceiver<Reload>> {
+    let config_files: Vec<PathBuf> = cli.config_path().iter().flat_map(|p| targets(p)).collect();
+    let mut css_files = css_files(&cli, configs);
+    let mut watch = Watch::new()?;
+    watch.add_files(&config_files)?;
+    watch.add_files(&css_files)?;
+    let (sender, receiver) = unbounded_channel();
+    std::thread::spawn(move || loop {
+        let mut changed = match watch.read(None) {
+            Ok(changed) => changed,
+            Err(err) => {
+                log::error!("file watcher failed: {err:?}");
+                return;
+            }
+        };
+        loop {
+            match watch.read(Some(DEBOUNCE)) {
+                Ok(more) if more.is_empty() => break,
+                Ok(more) => changed.extend(more),
+                Err(err) => {
+                    log::error!("file watcher failed: {err:?}");
+                    break;
+                }
+            }
+        }
+        if modified(&changed, &config_files) {
+            log::info!("config modified. reloading");
+            let configs = cli
+                .get_config()
+                .and_then(|config| cli.mode_configs(&config, &modes, &env_plugins));
+            match configs {
+                Ok(configs) => {
+                    css_files = self::css_files(&cli, &configs);
+                    if let Err(err) = watch.add_files(&css_files) {
+                        log::error!("failed to watch css: {err:?}");
+                    }
+                    if sender.send(Reload::Config(configs)).is_err() {
+                        return;
+                    }
+                }
+                Err(err) => log::error!("failed to reload config: {err:?}"),
+            }
+        }
+        if modified(&changed, &css_files) {
+            log::info!("css modified. reloading");
+            if sender.send(Reload::Css).is_err() {
+                return;
+            }
+        }
+    });
+    Ok(receiver)
+}